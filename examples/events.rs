@@ -1,16 +1,17 @@
 use bevy::{
-    log::{Level, LogPlugin},
     prelude::{
-        error, info, shape, warn, App, Assets, Camera3dBundle, Color, Commands, Entity,
-        EventReader, EventWriter, KeyCode, Local, Mesh, PbrBundle, PluginGroup, PointLight,
-        PointLightBundle, Query, Res, ResMut, StandardMaterial, TouchInput, Transform, Vec3,
+        info, shape, App, Assets, Camera3dBundle, Color, Commands, Entity, EulerRot, EventReader,
+        EventWriter, KeyCode, Mesh, PbrBundle, PointLight, PointLightBundle, Query, Res, ResMut,
+        StandardMaterial, Transform, Vec3, With,
     },
     DefaultPlugins,
 };
 use bevy_input::Input;
 use bevy_shape_draw::{
-    DrawShapeDebugPlugin, DrawShapeEvent, DrawShapeRaycastMesh, DrawShapeRaycastSource,
-    DrawStateEvent, DrawingboardEvent, Shape,
+    CopyShape, DeleteShape, DimensionLabels, DrawMode, DrawShapeDebugPlugin, DrawShapeEvent,
+    DrawShapeRaycastMesh, DrawShapeRaycastSource, DrawStateEvent, DrawingboardEvent,
+    DuplicateShape, PasteShape, RedoEvent, Selected, Shape, ShapeGizmoEvent, ShapePalette,
+    ShapeSelectionEvent, ShapeStyle, UndoEvent,
 };
 
 fn main() {
@@ -21,12 +22,18 @@ fn main() {
         ..Default::default()
     });
 
+    app.init_resource::<DimensionLabels>();
+
     app.add_startup_system(setup);
     app.add_system(spawned);
     app.add_system(finished);
     app.add_system(start_drawing);
     app.add_system(redraw_drawing);
     app.add_system(stop_drawing);
+    app.add_system(draw_mode);
+    app.add_system(draw_style);
+    app.add_system(undo_redo);
+    app.add_system(edit_selection);
     app.run();
 }
 
@@ -34,7 +41,17 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut palette: ResMut<ShapePalette>,
 ) {
+    palette.insert(
+        "hazard",
+        materials.add(Color::rgba(0.9, 0.2, 0.1, 0.6).into()),
+    );
+    palette.insert(
+        "storage",
+        materials.add(Color::rgba(0.2, 0.4, 0.9, 0.6).into()),
+    );
+
     // plane
     commands
         .spawn(PbrBundle {
@@ -77,26 +94,27 @@ fn setup(
 
 fn spawned(mut event_reader: EventReader<DrawShapeEvent>, query: Query<&Transform>) {
     for ev in event_reader.iter() {
-        match ev {
-            DrawShapeEvent::Spawned(e) => {
-                let transform = query.get(*e).unwrap();
+        if let DrawShapeEvent::Spawned(e) = ev {
+            // A shape that was cancelled right after it was spawned is gone already
+            if let Ok(transform) = query.get(*e) {
+                info!("New shape spawned at {}", transform.translation);
             }
-            _ => {}
         }
     }
 }
 
 fn finished(mut event_reader: EventReader<DrawShapeEvent>, query: Query<(&Transform, &Shape)>) {
     for ev in event_reader.iter() {
-        match ev {
-            DrawShapeEvent::Finished(e) => {
-                let (transform, shape) = query.get(*e).unwrap();
-                info!(
-                    "New Box finished at {} with shape and size {:?}",
-                    transform.translation, shape
-                );
-            }
-            _ => {}
+        if let DrawShapeEvent::Finished(e) = ev {
+            let (transform, shape) = query.get(*e).unwrap();
+            let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+            info!(
+                "New shape finished at {} rotated {:.1} degrees with shape and size {:?}",
+                transform.translation,
+                yaw.to_degrees(),
+                shape
+            );
+            info!("Footprint corners {:?}", shape.world_outline(transform));
         }
     }
 }
@@ -115,30 +133,34 @@ fn start_drawing(
 fn redraw_drawing(
     mut drawingboard_writer: EventWriter<DrawingboardEvent>,
     mut state_writer: EventWriter<DrawStateEvent>,
+    mut gizmo_writer: EventWriter<ShapeGizmoEvent>,
     mut shape_event: EventReader<DrawShapeEvent>,
-    mut last_shape: Local<Option<Entity>>,
+    mut selection_event: EventReader<ShapeSelectionEvent>,
+    selected: Query<Entity, With<Selected>>,
     keys: Res<Input<KeyCode>>,
 ) {
     for ev in shape_event.iter() {
         info!("{ev:?}");
-        match ev {
-            DrawShapeEvent::Spawned(e) => {
-                *last_shape = Some(*e);
-                info!("Last Shape Saved");
-            }
-            _ => {}
-        }
+    }
+    for ev in selection_event.iter() {
+        info!("{ev:?}");
     }
 
-    let e = match *last_shape {
-        Some(e) => e,
-        None => return,
+    let Ok(e) = selected.get_single() else {
+        return;
     };
 
     if keys.just_pressed(KeyCode::W) {
         drawingboard_writer.send(DrawingboardEvent::Spawn(0.0));
         state_writer.send(DrawStateEvent::Redraw(e));
     }
+
+    if keys.just_pressed(KeyCode::G) {
+        gizmo_writer.send(ShapeGizmoEvent::Attach(e));
+    }
+    if keys.just_pressed(KeyCode::H) {
+        gizmo_writer.send(ShapeGizmoEvent::Detach);
+    }
 }
 
 fn stop_drawing(
@@ -151,3 +173,77 @@ fn stop_drawing(
         state_writer.send(DrawStateEvent::Disable);
     }
 }
+
+fn draw_mode(mut state_writer: EventWriter<DrawStateEvent>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::F1) {
+        state_writer.send(DrawStateEvent::Mode(DrawMode::Box));
+    }
+    if keys.just_pressed(KeyCode::F2) {
+        state_writer.send(DrawStateEvent::Mode(DrawMode::Cylinder));
+    }
+    if keys.just_pressed(KeyCode::F3) {
+        state_writer.send(DrawStateEvent::Mode(DrawMode::Prism));
+    }
+    if keys.just_pressed(KeyCode::F4) {
+        state_writer.send(DrawStateEvent::Mode(DrawMode::OrientedBox));
+    }
+}
+
+fn draw_style(
+    mut state_writer: EventWriter<DrawStateEvent>,
+    mut selected: Query<&mut ShapeStyle, With<Selected>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let style = if keys.just_pressed(KeyCode::F5) {
+        ShapeStyle::Palette("hazard".into())
+    } else if keys.just_pressed(KeyCode::F6) {
+        ShapeStyle::Palette("storage".into())
+    } else if keys.just_pressed(KeyCode::F7) {
+        ShapeStyle::Color(Color::YELLOW)
+    } else if keys.just_pressed(KeyCode::F8) {
+        ShapeStyle::Default
+    } else {
+        return;
+    };
+    for mut shape_style in &mut selected {
+        *shape_style = style.clone();
+    }
+    state_writer.send(DrawStateEvent::Style(style));
+}
+
+fn undo_redo(
+    mut undo_writer: EventWriter<UndoEvent>,
+    mut redo_writer: EventWriter<RedoEvent>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::Z) {
+        undo_writer.send(UndoEvent);
+    }
+    if keys.just_pressed(KeyCode::Y) {
+        redo_writer.send(RedoEvent);
+    }
+}
+
+fn edit_selection(
+    mut delete_writer: EventWriter<DeleteShape>,
+    mut duplicate_writer: EventWriter<DuplicateShape>,
+    mut copy_writer: EventWriter<CopyShape>,
+    mut paste_writer: EventWriter<PasteShape>,
+    selected: Query<Entity, With<Selected>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    for e in &selected {
+        if keys.just_pressed(KeyCode::Delete) {
+            delete_writer.send(DeleteShape(e));
+        }
+        if keys.just_pressed(KeyCode::D) {
+            duplicate_writer.send(DuplicateShape(e, Vec3::new(0.2, 0., 0.2)));
+        }
+        if keys.just_pressed(KeyCode::C) {
+            copy_writer.send(CopyShape(e));
+        }
+    }
+    if keys.just_pressed(KeyCode::V) {
+        paste_writer.send(PasteShape(Vec3::new(0.4, 0., 0.4)));
+    }
+}
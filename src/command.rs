@@ -0,0 +1,144 @@
+use bevy::prelude::{
+    Assets, Commands, Entity, EventReader, EventWriter, Mesh, Query, Res, ResMut, Resource,
+    Transform, Vec3, Without,
+};
+
+use crate::{
+    draw::{DrawingState, Editing},
+    style::{AppearanceQuery, ShapeAppearance},
+    BoxDrawResources, DrawShapeEvent, Shape, ShapeHistory, ShapeSnapshot, ShapeUnits,
+};
+
+/// Despawns a drawn shape
+pub struct DeleteShape(pub Entity);
+
+/// Spawns a copy of a drawn shape, moved by the offset in the display unit of [`ShapeUnits`]
+pub struct DuplicateShape(pub Entity, pub Vec3);
+
+/// Puts a drawn shape on the [`ShapeClipboard`].
+/// Shapes copied in the same frame are put on the clipboard together
+pub struct CopyShape(pub Entity);
+
+/// Spawns copies of every shape on the [`ShapeClipboard`],
+/// moved by the offset in the display unit of [`ShapeUnits`]
+pub struct PasteShape(pub Vec3);
+
+/// A drawn shape together with the way it looks
+#[derive(Clone, Debug)]
+struct CopiedShape {
+    snapshot: ShapeSnapshot,
+    appearance: ShapeAppearance,
+}
+
+/// The shapes copied by the last [`CopyShape`]
+#[derive(Resource, Default)]
+pub struct ShapeClipboard {
+    shapes: Vec<CopiedShape>,
+}
+
+impl ShapeClipboard {
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn shape_commands(
+    mut delete_events: EventReader<DeleteShape>,
+    mut duplicate_events: EventReader<DuplicateShape>,
+    mut copy_events: EventReader<CopyShape>,
+    mut paste_events: EventReader<PasteShape>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut clipboard: ResMut<ShapeClipboard>,
+    mut history: ResMut<ShapeHistory>,
+    mut state: ResMut<DrawingState>,
+    resources: Res<BoxDrawResources>,
+    units: Res<ShapeUnits>,
+    shapes: Query<(&Shape, &Transform, AppearanceQuery), Without<Editing>>,
+    mut event_writer: EventWriter<DrawShapeEvent>,
+) {
+    // Shapes that are being drawn are left alone, like with an undo
+    let copy = |e: Entity| {
+        shapes
+            .get(e)
+            .ok()
+            .map(|(shape, transform, appearance)| CopiedShape {
+                snapshot: ShapeSnapshot {
+                    shape: shape.clone(),
+                    transform: *transform,
+                },
+                appearance: ShapeAppearance::new(appearance),
+            })
+    };
+
+    let mut copied = Vec::new();
+    for CopyShape(e) in copy_events.iter() {
+        if let Some(shape) = copy(*e) {
+            copied.push(shape);
+            event_writer.send(DrawShapeEvent::Copied(*e));
+        }
+    }
+    if !copied.is_empty() {
+        clipboard.shapes = copied;
+    }
+
+    for DuplicateShape(e, offset) in duplicate_events.iter() {
+        if let Some(shape) = copy(*e) {
+            let entity = spawn_copy(
+                shape,
+                *offset * units.scale(),
+                &mut commands,
+                &mut meshes,
+                &resources,
+                &mut history,
+            );
+            event_writer.send(DrawShapeEvent::Duplicated(entity));
+        }
+    }
+
+    for PasteShape(offset) in paste_events.iter() {
+        for shape in &clipboard.shapes {
+            let entity = spawn_copy(
+                shape.clone(),
+                *offset * units.scale(),
+                &mut commands,
+                &mut meshes,
+                &resources,
+                &mut history,
+            );
+            event_writer.send(DrawShapeEvent::Pasted(entity));
+        }
+    }
+
+    for DeleteShape(e) in delete_events.iter() {
+        if let Some(shape) = copy(*e) {
+            history.record_with_appearance(*e, Some(shape.snapshot), None, shape.appearance);
+            commands.entity(*e).despawn();
+            state.clear_redraw(*e);
+            event_writer.send(DrawShapeEvent::Deleted(*e));
+        }
+    }
+}
+
+fn spawn_copy(
+    shape: CopiedShape,
+    offset: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    resources: &BoxDrawResources,
+    history: &mut ShapeHistory,
+) -> Entity {
+    let mut snapshot = shape.snapshot;
+    snapshot.transform.translation += offset;
+
+    let entity = shape
+        .appearance
+        .spawn(snapshot.clone(), commands, meshes, resources);
+    history.record_with_appearance(entity, None, Some(snapshot), shape.appearance);
+    entity
+}
@@ -0,0 +1,362 @@
+use bevy::prelude::{
+    Assets, Color, Commands, Component, DetectChanges, Entity, EventReader, FromWorld,
+    GlobalTransform, Handle, KeyCode, Local, Mesh, PbrBundle, Query, ReceivedCharacter, Res,
+    ResMut, Resource, StandardMaterial, Transform, Vec3, With, Without, World,
+};
+use bevy_input::Input;
+
+use crate::{draw::Editing, mesh, DrawInputSettings, DrawShapeRaycastSource, Shape, ShapeUnits};
+
+/// Shows the size of the shape that is being drawn as labels next to its edges
+/// while this resource exists. Sizes are given in meters and scaled by
+/// [`ShapeUnits::world_units_per_meter`].
+/// Without it only the values typed into [`TypedDimensions`] are shown, with the default look
+#[derive(Resource, Clone, Debug)]
+pub struct DimensionLabels {
+    /// Turns a length in world units into the text of a label. Labels are drawn with a simple
+    /// stroke font that only has digits, spaces, `.`, `-`, `_`, `'`, `"` and the letters of the
+    /// [`LengthUnit`](crate::LengthUnit) symbols: `c`, `f`, `i`, `m`, `n` and `t`.
+    /// Any other character is left blank
+    pub format: fn(f32, &ShapeUnits) -> String,
+    /// The height of the text
+    pub text_size: f32,
+    /// The distance between a label and the edge it measures
+    pub offset: f32,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for DimensionLabels {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world
+            .get_resource_mut::<Assets<StandardMaterial>>()
+            .unwrap();
+        Self::new(&mut materials)
+    }
+}
+
+impl DimensionLabels {
+    fn new(materials: &mut Assets<StandardMaterial>) -> Self {
+        let material = materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            unlit: true,
+            ..Default::default()
+        });
+
+        Self {
+            format: |length, units| units.format(length),
+            text_size: 0.08,
+            offset: 0.05,
+            material,
+        }
+    }
+}
+
+/// The measurement a [`DimensionLabel`] shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dimension {
+    /// Along the local X axis of a box
+    Width,
+    /// Along the local Z axis of a box
+    Depth,
+    Height,
+    /// Of a cylinder
+    Diameter,
+}
+
+/// A label showing one dimension of the shape that is being drawn
+#[derive(Component, Clone, Debug)]
+pub struct DimensionLabel {
+    pub shape: Entity,
+    pub dimension: Dimension,
+    /// The length in the display unit of [`ShapeUnits`]
+    pub length: f32,
+    pub text: String,
+}
+
+/// What has been typed for the dimensions of the shape that is being drawn, added to the shape
+/// when a number is typed. Typed values are in the display unit of [`ShapeUnits`] and take
+/// precedence over the pointer, with the point where drawing started kept in place
+#[derive(Component, Clone, Debug)]
+pub struct TypedDimensions {
+    /// Every dimension of the shape with the text typed for it, empty until something is typed
+    pub fields: Vec<(Dimension, String)>,
+    /// The index of the field that is typed into
+    pub active: usize,
+}
+
+impl TypedDimensions {
+    fn new(shape: &Shape) -> Self {
+        Self {
+            fields: dimensions(shape, 0.)
+                .into_iter()
+                .map(|(dimension, ..)| (dimension, String::new()))
+                .collect(),
+            active: 0,
+        }
+    }
+
+    /// The typed value of the dimension in the display unit, if it is a positive number
+    pub fn value(&self, dimension: Dimension) -> Option<f32> {
+        self.fields
+            .iter()
+            .find(|(d, _)| *d == dimension)
+            .and_then(|(_, text)| text.parse::<f32>().ok())
+            .filter(|value| *value > 0.)
+    }
+
+    /// The text shown in the label of the dimension while typing,
+    /// with a cursor after the field that is typed into
+    fn label(&self, dimension: Dimension) -> Option<String> {
+        let i = self.fields.iter().position(|(d, _)| *d == dimension)?;
+        let text = &self.fields[i].1;
+        if i == self.active {
+            Some(format!("{text}_"))
+        } else {
+            (!text.is_empty()).then(|| text.clone())
+        }
+    }
+}
+
+/// Sets the typed dimensions of a shape that is being drawn from `origin`
+pub(crate) fn apply_typed_dimensions(
+    typed: &TypedDimensions,
+    units: &ShapeUnits,
+    origin: Vec3,
+    shape: &mut Shape,
+    transform: &mut Transform,
+) {
+    let value = |dimension| typed.value(dimension).map(|value| units.to_world(value));
+    let base = transform.translation.y - shape.height() / 2.;
+
+    match shape {
+        Shape::Box(size) => {
+            let (width, depth) = (value(Dimension::Width), value(Dimension::Depth));
+            if width.is_some() || depth.is_some() {
+                // The box grows away from the corner where drawing started,
+                // or around it when the box is drawn from its center
+                let corner = transform.rotation.inverse() * (origin - transform.translation);
+                let side = |v: f32| {
+                    if v.abs() <= f32::EPSILON {
+                        0.
+                    } else {
+                        v.signum()
+                    }
+                };
+                size.x = width.unwrap_or(size.x);
+                size.z = depth.unwrap_or(size.z);
+                let local = Vec3::new(side(corner.x) * size.x, 0., side(corner.z) * size.z) / 2.;
+                let center = origin - transform.rotation * local;
+                transform.translation.x = center.x;
+                transform.translation.z = center.z;
+            }
+        }
+        Shape::Cylinder { radius, .. } => {
+            if let Some(diameter) = value(Dimension::Diameter) {
+                *radius = diameter / 2.;
+            }
+        }
+        Shape::Prism { .. } => {}
+    }
+
+    if let Some(height) = value(Dimension::Height) {
+        shape.set_height(height);
+        transform.translation.y = base + height / 2.;
+    }
+}
+
+/// Reads numbers typed while a shape is drawn into its [`TypedDimensions`] and resizes it
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn type_dimensions(
+    mut commands: Commands,
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<DrawInputSettings>,
+    units: Res<ShapeUnits>,
+    mut shapes: Query<(
+        Entity,
+        &Editing,
+        &mut Shape,
+        &mut Transform,
+        &Handle<Mesh>,
+        Option<&mut TypedDimensions>,
+    )>,
+    finished: Query<Entity, (With<TypedDimensions>, Without<Editing>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for e in &finished {
+        commands.entity(e).remove::<TypedDimensions>();
+    }
+
+    let typed = characters
+        .iter()
+        .filter_map(|ev| match ev.char {
+            c @ ('0'..='9' | '.') => Some(c),
+            ',' => Some('.'),
+            _ => None,
+        })
+        .collect::<String>();
+    let Ok((e, edit, mut shape, mut transform, handle, fields)) = shapes.get_single_mut() else {
+        return;
+    };
+    if !settings.typed_dimensions {
+        return;
+    }
+
+    let Some(mut fields) = fields else {
+        if !typed.is_empty() {
+            let mut fields = TypedDimensions::new(&shape);
+            if let Some((_, text)) = fields.fields.first_mut() {
+                *text = typed;
+            }
+            commands.entity(e).insert(fields);
+        }
+        return;
+    };
+
+    if keyboard.any_just_pressed(settings.next_field_keys.iter().copied()) {
+        fields.active = (fields.active + 1) % fields.fields.len().max(1);
+    }
+    let active = fields.active;
+    if let Some((_, text)) = fields.fields.get_mut(active) {
+        if keyboard.any_just_pressed(settings.erase_keys.iter().copied()) {
+            text.pop();
+        }
+        text.push_str(&typed);
+    }
+
+    // Only written when they differ so that the shape is not marked as changed every frame
+    let (mut new_shape, mut new_transform) = (shape.clone(), *transform);
+    apply_typed_dimensions(
+        &fields,
+        &units,
+        edit.origin,
+        &mut new_shape,
+        &mut new_transform,
+    );
+    if new_shape != *shape {
+        if let Some(mesh) = meshes.get_mut(handle) {
+            *mesh = new_shape.mesh();
+        }
+        *shape = new_shape;
+    }
+    if new_transform != *transform {
+        *transform = new_transform;
+    }
+}
+
+/// The dimensions of the shape and where their labels are in the local space of the shape
+fn dimensions(shape: &Shape, offset: f32) -> Vec<(Dimension, f32, Vec3)> {
+    let half = shape.height() / 2.;
+    match shape {
+        Shape::Box(size) => {
+            let (x, z) = (size.x / 2. + offset, size.z / 2. + offset);
+            vec![
+                (Dimension::Width, size.x, Vec3::new(0., -half, z)),
+                (Dimension::Depth, size.z, Vec3::new(x, -half, 0.)),
+                (Dimension::Height, size.y, Vec3::new(x, 0., z)),
+            ]
+        }
+        Shape::Cylinder { radius, height } => vec![
+            (
+                Dimension::Diameter,
+                radius * 2.,
+                Vec3::new(0., -half, radius + offset),
+            ),
+            (
+                Dimension::Height,
+                *height,
+                Vec3::new(radius + offset, 0., 0.),
+            ),
+        ],
+        Shape::Prism { height, .. } => {
+            let footprint = shape.footprint();
+            vec![(
+                Dimension::Height,
+                *height,
+                Vec3::new(footprint.x / 2. + offset, 0., footprint.y / 2. + offset),
+            )]
+        }
+    }
+}
+
+/// Spawns, moves and updates the labels of the shapes that are being drawn,
+/// turned towards the camera
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn update_dimension_labels(
+    mut commands: Commands,
+    settings: Option<Res<DimensionLabels>>,
+    mut typed_only: Local<Option<DimensionLabels>>,
+    units: Res<ShapeUnits>,
+    shapes: Query<(Entity, &Shape, &Transform, Option<&TypedDimensions>), With<Editing>>,
+    mut labels: Query<(Entity, &mut DimensionLabel, &mut Transform, &Handle<Mesh>), Without<Shape>>,
+    cameras: Query<&GlobalTransform, With<DrawShapeRaycastSource>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // What is typed is always shown, so that it is never typed blind
+    let all = settings.is_some();
+    let (settings, refresh) = match &settings {
+        Some(settings) => (&**settings, settings.is_changed() || units.is_changed()),
+        None => (
+            &*typed_only.get_or_insert_with(|| DimensionLabels::new(&mut materials)),
+            units.is_changed(),
+        ),
+    };
+
+    let camera_rotation = cameras
+        .iter()
+        .next()
+        .map(|camera| camera.compute_transform().rotation);
+
+    let text_size = units.from_meters(settings.text_size);
+
+    let mut wanted = Vec::new();
+    for (e, shape, transform, typed) in &shapes {
+        for (dimension, length, anchor) in dimensions(shape, units.from_meters(settings.offset)) {
+            let placement = Transform::from_translation(transform.transform_point(anchor))
+                .with_rotation(camera_rotation.unwrap_or(transform.rotation));
+            // What is being typed is shown instead of the length
+            let text = match typed.and_then(|typed| typed.label(dimension)) {
+                Some(text) => text,
+                None if all => (settings.format)(length, &units),
+                None => continue,
+            };
+            wanted.push((e, dimension, length, text, placement));
+        }
+    }
+
+    for (e, mut label, mut transform, handle) in &mut labels {
+        let Some(i) = wanted.iter().position(|(shape, dimension, ..)| {
+            *shape == label.shape && *dimension == label.dimension
+        }) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        let (_, _, length, text, placement) = wanted.swap_remove(i);
+        *transform = placement;
+        if text != label.text || refresh {
+            label.length = units.to_display(length);
+            label.text = text;
+            if let Some(mesh) = meshes.get_mut(handle) {
+                *mesh = mesh::text(&label.text, text_size);
+            }
+        }
+    }
+
+    for (shape, dimension, length, text, placement) in wanted {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(mesh::text(&text, text_size)),
+                material: settings.material.clone(),
+                transform: placement,
+                ..Default::default()
+            },
+            DimensionLabel {
+                shape,
+                dimension,
+                length: units.to_display(length),
+                text,
+            },
+        ));
+    }
+}
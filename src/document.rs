@@ -0,0 +1,271 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::{
+    error, Assets, Commands, Entity, EventReader, EventWriter, Mesh, Query, Res, ResMut, Transform,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    draw::{DrawingState, Editing},
+    style::ShapeAppearance,
+    BoxDrawResources, DrawShapeEvent, Shape, ShapeHistory, ShapeMetadata, ShapeSnapshot,
+    ShapeStyle,
+};
+
+/// Saves every drawn shape to the file, as JSON if the extension is `json` and RON otherwise
+pub struct SaveShapes(pub PathBuf);
+
+/// Replaces every drawn shape with the shapes in the file,
+/// read as JSON if the extension is `json` and RON otherwise
+pub struct LoadShapes(pub PathBuf);
+
+/// A single shape in a [`ShapeDocument`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DocumentShape {
+    pub shape: Shape,
+    pub transform: Transform,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ShapeMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<ShapeStyle>,
+}
+
+/// The drawn shapes in a form that can be saved and loaded
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShapeDocument {
+    pub shapes: Vec<DocumentShape>,
+}
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(io::Error),
+    Ron(ron::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Io(e) => write!(f, "could not access the shape document: {e}"),
+            DocumentError::Ron(e) => write!(f, "invalid RON shape document: {e}"),
+            DocumentError::Json(e) => write!(f, "invalid JSON shape document: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<io::Error> for DocumentError {
+    fn from(e: io::Error) -> Self {
+        DocumentError::Io(e)
+    }
+}
+
+impl From<ron::Error> for DocumentError {
+    fn from(e: ron::Error) -> Self {
+        DocumentError::Ron(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for DocumentError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        DocumentError::Ron(e.code)
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(e: serde_json::Error) -> Self {
+        DocumentError::Json(e)
+    }
+}
+
+impl ShapeDocument {
+    pub fn to_ron(&self) -> Result<String, DocumentError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn from_ron(s: &str) -> Result<Self, DocumentError> {
+        Ok(ron::from_str(s)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, DocumentError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Writes the document as JSON if the extension is `json` and RON otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DocumentError> {
+        let path = path.as_ref();
+        let contents = if is_json(path) {
+            self.to_json()?
+        } else {
+            self.to_ron()?
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Reads the document as JSON if the extension is `json` and RON otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DocumentError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        if is_json(path) {
+            Self::from_json(&contents)
+        } else {
+            Self::from_ron(&contents)
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("json"))
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn shape_documents(
+    mut save_events: EventReader<SaveShapes>,
+    mut load_events: EventReader<LoadShapes>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut history: ResMut<ShapeHistory>,
+    mut state: ResMut<DrawingState>,
+    resources: Res<BoxDrawResources>,
+    shapes: Query<(
+        Entity,
+        &Shape,
+        &Transform,
+        Option<&ShapeMetadata>,
+        Option<&ShapeStyle>,
+        Option<&Editing>,
+    )>,
+    mut event_writer: EventWriter<DrawShapeEvent>,
+) {
+    for SaveShapes(path) in save_events.iter() {
+        let document = ShapeDocument {
+            shapes: shapes
+                .iter()
+                .filter_map(|(_, shape, transform, metadata, style, edit)| {
+                    // Shapes being drawn are saved the way they were before the drawing started
+                    let (shape, transform) = match edit {
+                        Some(edit) => {
+                            let original = edit.original.as_ref()?;
+                            (&original.shape, &original.transform)
+                        }
+                        None => (shape, transform),
+                    };
+                    Some(DocumentShape {
+                        shape: shape.clone(),
+                        transform: *transform,
+                        metadata: metadata.cloned(),
+                        style: style.cloned(),
+                    })
+                })
+                .collect(),
+        };
+        if let Err(e) = document.save(path) {
+            error!("Failed to save shapes to {}: {e}", path.display());
+        }
+    }
+
+    let Some(LoadShapes(path)) = load_events.iter().last() else {
+        return;
+    };
+    let document = match ShapeDocument::load(path) {
+        Ok(document) => document,
+        Err(e) => {
+            error!("Failed to load shapes from {}: {e}", path.display());
+            return;
+        }
+    };
+
+    // The history refers to the shapes that are replaced
+    history.clear();
+    for (e, ..) in &shapes {
+        commands.entity(e).despawn();
+        state.clear_redraw(e);
+        event_writer.send(DrawShapeEvent::Despawned(e));
+    }
+
+    for shape in document.shapes {
+        let appearance = ShapeAppearance {
+            material: None,
+            metadata: shape.metadata,
+            style: shape.style,
+        };
+        let snapshot = ShapeSnapshot {
+            shape: shape.shape,
+            transform: shape.transform,
+        };
+        let e = appearance.spawn(snapshot, &mut commands, &mut meshes, &resources);
+        event_writer.send(DrawShapeEvent::Loaded(e));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use bevy::{
+        prelude::{Color, Quat, Vec2, Vec3},
+        utils::HashMap,
+    };
+
+    use super::*;
+
+    fn document() -> ShapeDocument {
+        ShapeDocument {
+            shapes: vec![
+                DocumentShape {
+                    shape: Shape::Box(Vec3::new(1., 2., 3.)),
+                    transform: Transform::from_xyz(1.5, 1., -2.)
+                        .with_rotation(Quat::from_rotation_y(0.3)),
+                    metadata: Some(ShapeMetadata(HashMap::from_iter([
+                        ("zone".to_string(), "hazard".to_string()),
+                        ("note".to_string(), "a \"quoted\" value".to_string()),
+                    ]))),
+                    style: Some(ShapeStyle::Palette("walls".into())),
+                },
+                DocumentShape {
+                    shape: Shape::Prism {
+                        points: vec![Vec2::ZERO, Vec2::new(2., 0.), Vec2::new(0.5, 1.25)],
+                        height: 0.75,
+                    },
+                    transform: Transform::from_xyz(-3., 0.375, 4.),
+                    metadata: None,
+                    style: Some(ShapeStyle::Color(Color::rgba(0.2, 0.4, 0.6, 0.5))),
+                },
+                DocumentShape {
+                    shape: Shape::Cylinder {
+                        radius: 0.5,
+                        height: 1.,
+                    },
+                    transform: Transform::IDENTITY,
+                    metadata: None,
+                    style: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let document = document();
+        let ron = document.to_ron().unwrap();
+        assert_eq!(ShapeDocument::from_ron(&ron).unwrap(), document);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let document = document();
+        let json = document.to_json().unwrap();
+        assert_eq!(ShapeDocument::from_json(&json).unwrap(), document);
+    }
+}
@@ -1,13 +1,22 @@
-use bevy::prelude::{
-    debug, shape, warn, AlphaMode, Assets, Commands, Component, Entity, EventReader, EventWriter,
-    FromWorld, Handle, Local, Mesh, MouseButton, PbrBundle, Query, Res, ResMut, Resource,
-    StandardMaterial, TouchInput, Transform, Vec3, With, World,
+use bevy::{
+    prelude::{
+        debug, shape, Added, AlphaMode, Assets, Changed, Commands, Component, Deref, DerefMut,
+        Entity, EventReader, EventWriter, FromReflect, FromWorld, Handle, Mesh, PbrBundle, Quat,
+        Query, Reflect, ReflectComponent, Res, ResMut, Resource, StandardMaterial, Time, Transform,
+        Vec2, Vec3, With, Without, World,
+    },
+    render::primitives::Aabb,
+    utils::HashMap,
 };
-use bevy_input::{touch::TouchPhase, Input};
-use bevy_mod_raycast::Intersection;
+use bevy_mod_raycast::Primitive3d;
 
-use crate::ShapeDrawRaycastSet;
+use crate::{
+    dimension::apply_typed_dimensions, input::PointerInput, mesh, style::DrawStyle,
+    DrawShapeRaycastMesh, DrawShapeRaycastSource, ShapeSnapshot, ShapeStyle, ShapeUnits,
+    TypedDimensions,
+};
 
+/// Lengths are given in the display unit of [`ShapeUnits`](crate::ShapeUnits)
 #[derive(Resource)]
 pub struct BoxDrawResources {
     pub material: Handle<StandardMaterial>,
@@ -15,6 +24,20 @@ pub struct BoxDrawResources {
     pub initial_size: f32,
     /// The box will start with an initial height
     pub initial_height: f32,
+    /// Shapes with a footprint smaller than this in either direction when released are removed
+    pub min_size: Vec2,
+    /// Clicking within this distance of the first point of a polygon closes it
+    pub polygon_close_distance: f32,
+    /// Two clicks within this many seconds on the same point closes a polygon
+    pub double_click_time: f32,
+    /// When enabled the height is set by moving the pointer up or down after the footprint
+    /// is drawn, and committed by clicking again
+    pub height_phase: bool,
+    /// When enabled the point where drawing starts is the center of a box rather than a corner,
+    /// and the pointer sets half of its size in both directions.
+    /// [`DrawInputSettings::center_keys`](crate::DrawInputSettings::center_keys) do the same
+    /// for a single shape when held as drawing starts
+    pub from_center: bool,
 }
 
 impl FromWorld for BoxDrawResources {
@@ -39,6 +62,11 @@ impl FromWorld for BoxDrawResources {
             material,
             initial_size: 0.01,
             initial_height: 0.2,
+            min_size: Vec2::splat(0.05),
+            polygon_close_distance: 0.1,
+            double_click_time: 0.3,
+            height_phase: false,
+            from_center: false,
         }
     }
 }
@@ -52,12 +80,37 @@ pub(crate) fn keep_enabled(
     }
 }
 
+/// The events from [`DrawShapeEvent::Spawned`] to [`DrawShapeEvent::Discarded`] are about the
+/// shape being drawn, and are sent the frame after they happen
 #[derive(Copy, Clone, Debug)]
 pub enum DrawShapeEvent {
-    /// Spawned is sent when a new shape is drawn, containing the newly created entity
+    /// Spawned is sent when a new shape is drawn, containing the newly created entity.
+    /// The entity is already gone if the shape was cancelled or discarded right away
     Spawned(Entity),
     Redrawing(Entity),
     Finished(Entity),
+    /// The shape being drawn was cancelled. A new shape is despawned and a redrawn shape
+    /// gets back its original geometry
+    Cancelled(Entity),
+    /// The shape was smaller than [`BoxDrawResources::min_size`] when released. A new shape is
+    /// despawned and a redrawn shape gets back its original geometry
+    Discarded(Entity),
+    /// The shape was moved or resized with the gizmo
+    Modified(Entity),
+    /// The shape was changed or spawned again by an undo or redo
+    Restored(Entity),
+    /// The shape was despawned by an undo or redo, or replaced by a loaded document
+    Despawned(Entity),
+    /// The shape was despawned by a [`crate::DeleteShape`]
+    Deleted(Entity),
+    /// The shape was spawned by a [`crate::DuplicateShape`], containing the new entity
+    Duplicated(Entity),
+    /// The shape was copied by a [`crate::CopyShape`]
+    Copied(Entity),
+    /// The shape was spawned by a [`crate::PasteShape`], containing the new entity
+    Pasted(Entity),
+    /// The shape was spawned from a loaded document or an imported file, containing the new entity
+    Loaded(Entity),
 }
 
 pub enum DrawStateEvent {
@@ -65,262 +118,824 @@ pub enum DrawStateEvent {
     /// Enables Drawing if disabled and will use the provided entity to store the shape
     Redraw(Entity),
     Disable,
+    /// Sets the kind of shape drawn from now on
+    Mode(DrawMode),
+    /// Sets the style of shapes drawn from now on
+    Style(ShapeStyle),
 }
 
 /// This component is added to everything drawn within this plugin.
 /// It contains the shape and the size of the object
-#[derive(Debug, Component)]
+#[derive(Debug, Clone, PartialEq, Component, Reflect, FromReflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Shape {
     Box(Vec3),
+    /// A vertical cylinder centered on the entity's translation
+    Cylinder {
+        radius: f32,
+        height: f32,
+    },
+    /// A polygon footprint in the XZ plane, relative to the entity's translation,
+    /// extruded to the given height
+    Prism {
+        points: Vec<Vec2>,
+        height: f32,
+    },
 }
 
-#[derive(Resource)]
+/// A unit box, which is what a [`Shape`] starts as when it is created from reflection
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::Box(Vec3::ONE)
+    }
+}
+
+impl Shape {
+    pub fn height(&self) -> f32 {
+        match self {
+            Shape::Box(size) => size.y,
+            Shape::Cylinder { height, .. } => *height,
+            Shape::Prism { height, .. } => *height,
+        }
+    }
+
+    pub fn set_height(&mut self, value: f32) {
+        match self {
+            Shape::Box(size) => size.y = value,
+            Shape::Cylinder { height, .. } => *height = value,
+            Shape::Prism { height, .. } => *height = value,
+        }
+    }
+
+    /// The size of the shape along the local X and Z axes
+    pub fn footprint(&self) -> Vec2 {
+        match self {
+            Shape::Box(size) => Vec2::new(size.x, size.z),
+            Shape::Cylinder { radius, .. } => Vec2::splat(radius * 2.),
+            Shape::Prism { points, .. } => {
+                let min = points
+                    .iter()
+                    .fold(Vec2::splat(f32::INFINITY), |a, b| a.min(*b));
+                let max = points
+                    .iter()
+                    .fold(Vec2::splat(f32::NEG_INFINITY), |a, b| a.max(*b));
+                (max - min).max(Vec2::ZERO)
+            }
+        }
+    }
+
+    /// The corners of the footprint in the local XZ plane.
+    /// A cylinder is approximated by a polygon
+    pub fn outline(&self) -> Vec<Vec2> {
+        match self {
+            Shape::Box(size) => {
+                let (x, z) = (size.x / 2., size.z / 2.);
+                vec![
+                    Vec2::new(-x, -z),
+                    Vec2::new(x, -z),
+                    Vec2::new(x, z),
+                    Vec2::new(-x, z),
+                ]
+            }
+            Shape::Cylinder { radius, .. } => (0..32)
+                .map(|i| {
+                    let angle = i as f32 / 32. * std::f32::consts::TAU;
+                    Vec2::new(angle.cos(), angle.sin()) * *radius
+                })
+                .collect(),
+            Shape::Prism { points, .. } => points.clone(),
+        }
+    }
+
+    /// The corners of the footprint in world space at the bottom of the shape,
+    /// taking the rotation of the transform into account
+    pub fn world_outline(&self, transform: &Transform) -> Vec<Vec3> {
+        let bottom = -self.height() / 2.;
+        self.outline()
+            .into_iter()
+            .map(|p| transform.transform_point(Vec3::new(p.x, bottom, p.y)))
+            .collect()
+    }
+
+    /// Creates a mesh for the shape, centered around the origin
+    pub fn mesh(&self) -> Mesh {
+        match self {
+            Shape::Box(size) => Mesh::from(shape::Box::new(size.x, size.y, size.z)),
+            Shape::Cylinder { radius, height } => Mesh::from(shape::Cylinder {
+                radius: *radius,
+                height: *height,
+                resolution: 32,
+                ..Default::default()
+            }),
+            Shape::Prism { points, height } => mesh::prism(points, *height),
+        }
+    }
+}
+
+/// Information about a drawn shape that is up to the app, which follows the shape when it is
+/// duplicated, copied or brought back by an undo
+#[derive(Debug, Clone, Default, PartialEq, Component, Reflect, FromReflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeMetadata(pub HashMap<String, String>);
+
+/// The kind of shape that is drawn when drawing is enabled.
+/// Redrawing an existing entity will always keep the kind of its current shape
+#[derive(Resource, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DrawMode {
+    /// The press point is one corner and the drag sets the opposite corner
+    #[default]
+    Box,
+    /// The press point is the center and the drag sets the radius
+    Cylinder,
+    /// Every click adds a point to the footprint. Clicking the first point again or
+    /// double-clicking closes the polygon
+    Prism,
+    /// The first two clicks define one edge of a box at any angle and the third sets its width
+    OrientedBox,
+}
+
+#[derive(Resource, Default)]
 pub(crate) enum DrawingState {
     Idle(Option<Entity>),
+    #[default]
     Disabled,
 }
 
-impl Default for DrawingState {
-    fn default() -> Self {
-        Self::Disabled
+impl DrawingState {
+    /// Stops waiting to redraw the entity when it is despawned, so that the next shape is new
+    pub(crate) fn clear_redraw(&mut self, e: Entity) {
+        if matches!(self, DrawingState::Idle(Some(redraw)) if *redraw == e) {
+            *self = DrawingState::Idle(None);
+        }
     }
 }
 
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub(crate) struct Editing {
+    /// The point where drawing started
+    pub origin: Vec3,
+    /// The geometry of a redrawn shape from before the redraw
+    pub original: Option<ShapeSnapshot>,
+    /// Whether a box is drawn from its center, decided when drawing starts
+    pub from_center: bool,
+}
+
+/// Added when the footprint is done and [`BoxDrawResources::height_phase`] is enabled.
+/// Contains the y coordinate of the bottom of the shape
 #[derive(Component)]
-pub(crate) struct Editing(pub Vec3);
+pub(crate) struct HeightDraft(f32);
+
+/// The points clicked so far while drawing a [`Shape::Prism`] or an oriented [`Shape::Box`]
+#[derive(Component)]
+pub(crate) struct PointDraft {
+    points: Vec<Vec3>,
+    last_click: f64,
+}
+
+/// Events about the shape being drawn that are sent in the next frame
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct DrawEventQueue(Vec<DrawShapeEvent>);
+
+/// We wait one frame before sending out the events to give time to spawn the entity
+pub(crate) fn send_draw_events(
+    mut event_queue: ResMut<DrawEventQueue>,
+    mut event_writer: EventWriter<DrawShapeEvent>,
+) {
+    for ev in event_queue.drain(..) {
+        event_writer.send(ev);
+    }
+}
+
+/// The components every drawn shape is spawned with
+pub(crate) fn shape_bundle(
+    shape: Shape,
+    transform: Transform,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+) -> (PbrBundle, Shape, DrawShapeRaycastMesh) {
+    (
+        PbrBundle {
+            mesh,
+            material,
+            transform,
+            ..Default::default()
+        },
+        shape,
+        DrawShapeRaycastMesh::default(),
+    )
+}
+
+/// Shapes spawned from a scene only come with what is reflected, so the mesh is generated from
+/// the [`Shape`] together with the rest of what a drawn shape is spawned with
+#[allow(clippy::type_complexity)]
+pub(crate) fn restore_shape_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    resources: Res<BoxDrawResources>,
+    shapes: Query<
+        (
+            Entity,
+            &Shape,
+            &Transform,
+            Option<&Handle<Mesh>>,
+            Option<&Handle<StandardMaterial>>,
+        ),
+        Added<Shape>,
+    >,
+) {
+    for (e, shape, transform, mesh, material) in &shapes {
+        if mesh.map_or(false, |mesh| meshes.contains(mesh)) {
+            continue;
+        }
+        let mesh = meshes.add(shape.mesh());
+        let material = material
+            .filter(|material| materials.contains(material))
+            .map_or_else(|| resources.material.clone(), Clone::clone);
+        commands
+            .entity(e)
+            .insert(shape_bundle(shape.clone(), *transform, mesh, material));
+    }
+}
+
+/// The bounding box of a mesh is only calculated once, so it is removed whenever the shape
+/// changes to be calculated again. Otherwise shapes are culled and missed by raycasts
+/// based on the size they were spawned with
+pub(crate) fn update_shape_bounds(
+    mut commands: Commands,
+    shapes: Query<Entity, (Changed<Shape>, With<Aabb>)>,
+) {
+    for e in &shapes {
+        commands.entity(e).remove::<Aabb>();
+    }
+}
 
 pub(crate) fn draw_state(
     mut event_reader: EventReader<DrawStateEvent>,
     mut state: ResMut<DrawingState>,
+    mut mode: ResMut<DrawMode>,
+    mut style: ResMut<DrawStyle>,
 ) {
     for ev in event_reader.iter() {
         match ev {
             DrawStateEvent::Redraw(e) => *state = DrawingState::Idle(Some(*e)),
             DrawStateEvent::Enable => *state = DrawingState::Idle(None),
             DrawStateEvent::Disable => *state = DrawingState::Disabled,
+            DrawStateEvent::Mode(m) => *mode = *m,
+            DrawStateEvent::Style(s) => style.0 = s.clone(),
         }
     }
 }
 
-#[derive(Resource, Default)]
-pub(crate) struct TouchId(Option<u64>);
-
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub(crate) fn draw_box(
     mut meshes: ResMut<Assets<Mesh>>,
-    query: Query<&Intersection<ShapeDrawRaycastSet>>,
-    keys: Res<Input<MouseButton>>,
+    pointer: Res<PointerInput>,
     resources: Res<BoxDrawResources>,
     mut commands: Commands,
-    edit_box: Query<Entity, With<Editing>>,
-    shapes: Query<&Shape>,
-    mut event_writer: EventWriter<DrawShapeEvent>,
-    mut touch_events: EventReader<TouchInput>,
-    mut event_queue: Local<Vec<DrawShapeEvent>>,
+    edit_box: Query<
+        (Entity, &Transform, &Shape, &Editing, &Handle<Mesh>),
+        (With<Editing>, Without<PointDraft>, Without<HeightDraft>),
+    >,
+    height_drafts: Query<Entity, With<HeightDraft>>,
+    mut drafts: Query<(
+        Entity,
+        &mut PointDraft,
+        &Handle<Mesh>,
+        &mut Transform,
+        &Shape,
+        &Editing,
+        Option<&TypedDimensions>,
+    )>,
+    shapes: Query<(&Shape, &Transform), Without<PointDraft>>,
+    time: Res<Time>,
+    mut event_queue: ResMut<DrawEventQueue>,
     state: Res<DrawingState>,
-    mut touch_id: ResMut<TouchId>,
-    mut touch_started: Local<bool>,
+    mode: Res<DrawMode>,
+    style: Res<DrawStyle>,
+    units: Res<ShapeUnits>,
 ) {
-    // We wait one frame before sending out the event to give time to spawn the entity
-    let mut next_event = event_queue.pop();
-    while next_event.is_some() {
-        event_writer.send(next_event.unwrap());
-        next_event = event_queue.pop();
-    }
+    let initial_size = units.to_world(resources.initial_size);
+    let close_distance = units.to_world(resources.polygon_close_distance);
 
     let redraw = match *state {
         DrawingState::Idle(e) => e,
         _ => return,
     };
+    // An entity that was despawned before it was redrawn is replaced by a new drawing
+    let redraw = redraw.filter(|e| commands.get_entity(*e).is_some());
+
+    if pointer.cancel {
+        return;
+    }
 
-    let height = if let Some(e) = redraw {
-        let shape = shapes.get(e);
-        if let Ok(Shape::Box(dim)) = shape {
-            dim.y
-        } else {
-            resources.initial_height
+    let (height, mode) = match redraw.and_then(|e| shapes.get(e).ok()) {
+        Some((shape @ Shape::Box(_), transform)) if transform.rotation != Quat::IDENTITY => {
+            (shape.height(), DrawMode::OrientedBox)
         }
-    } else {
-        resources.initial_height
+        Some((shape @ Shape::Box(_), _)) => (shape.height(), DrawMode::Box),
+        Some((shape @ Shape::Cylinder { .. }, _)) => (shape.height(), DrawMode::Cylinder),
+        Some((shape @ Shape::Prism { .. }, _)) => (shape.height(), DrawMode::Prism),
+        None => (units.to_world(resources.initial_height), *mode),
     };
 
-    let mut started = keys.just_pressed(MouseButton::Left);
-    let mut ended = keys.just_released(MouseButton::Left);
+    let started = pointer.started;
+    let ended = pointer.ended;
+    let intersect_position = pointer.position;
 
-    for ev in touch_events.iter() {
-        if let Some(id) = touch_id.0 {
-            if id != ev.id {
-                continue;
-            }
+    if let Ok((e, mut draft, handle, mut transform, shape, edit, typed)) = drafts.get_single_mut() {
+        // Confirming finishes the shape with the points clicked so far
+        let confirm = pointer.confirm && !started;
+        if !started && !confirm {
+            return;
         }
-
-        match ev.phase {
-            TouchPhase::Started => {
-                touch_id.0 = Some(ev.id);
+        let last = draft.points[draft.points.len() - 1];
+        let Some(position) = intersect_position.or(confirm.then_some(last)) else {
+            return;
+        };
+
+        let now = time.elapsed_seconds_f64();
+        let first = draft.points[0];
+        let height = shape.height();
+
+        let done = match shape {
+            Shape::Prism { .. } => {
+                let double_click = now - draft.last_click < resources.double_click_time as f64
+                    && position.distance(last) < close_distance;
+                let close_to_first = position.distance(first) < close_distance;
+
+                if draft.points.len() >= 3 && (double_click || close_to_first || confirm) {
+                    // Center the footprint around the entity so that it moves and rotates as expected
+                    let min = draft
+                        .points
+                        .iter()
+                        .fold(Vec3::splat(f32::INFINITY), |a, b| a.min(*b));
+                    let max = draft
+                        .points
+                        .iter()
+                        .fold(Vec3::splat(f32::NEG_INFINITY), |a, b| a.max(*b));
+                    let center = (min + max) / 2.;
+                    let points = draft
+                        .points
+                        .iter()
+                        .map(|p| Vec2::new(p.x - center.x, p.z - center.z))
+                        .collect();
+
+                    Some((
+                        Shape::Prism { points, height },
+                        Transform::from_xyz(center.x, first.y + height / 2., center.z),
+                    ))
+                } else {
+                    if !double_click && !confirm {
+                        let point = match pointer.axis_lock {
+                            true => lock_to_axis(last, position),
+                            false => position,
+                        };
+                        draft.points.push(point);
+                    }
+                    None
+                }
             }
-            TouchPhase::Ended | TouchPhase::Cancelled => {
-                if touch_id.0.is_some() {
-                    ended = true;
-                    *touch_started = false;
-                    touch_id.0 = None;
+            _ => {
+                if draft.points.len() < 2 {
+                    let end = match pointer.axis_lock {
+                        true => lock_to_axis(first, position),
+                        false => position,
+                    };
+                    if !confirm && end.distance(first) > f32::EPSILON {
+                        draft.points.push(end);
+                    }
+                    None
+                } else {
+                    let mut width = edge_offset(first, draft.points[1], position);
+                    if pointer.square {
+                        width = square_width(first, draft.points[1], width);
+                    }
+                    oriented_box(first, draft.points[1], width, edit.from_center).map(
+                        |(size, rotation, center)| {
+                            let mut shape = Shape::Box(Vec3::new(size.x, height, size.y));
+                            let mut transform =
+                                Transform::from_xyz(center.x, first.y + height / 2., center.z)
+                                    .with_rotation(rotation);
+                            if let Some(typed) = typed {
+                                apply_typed_dimensions(
+                                    typed,
+                                    &units,
+                                    first,
+                                    &mut shape,
+                                    &mut transform,
+                                );
+                            }
+                            (shape, transform)
+                        },
+                    )
                 }
             }
-            TouchPhase::Moved => {
-                started = !*touch_started;
-                *touch_started = true;
+        };
+        draft.last_click = now;
+
+        if let Some((shape, new_transform)) = done {
+            if too_small(&shape, &resources, &units) {
+                revert(e, edit, handle, &mut meshes, &mut commands);
+                event_queue.push(DrawShapeEvent::Discarded(e));
+                return;
+            }
+
+            if let Some(mesh) = meshes.get_mut(handle) {
+                *mesh = shape.mesh();
             }
+            *transform = new_transform;
+
+            commands.entity(e).remove::<PointDraft>().insert(shape);
+            if resources.height_phase {
+                commands.entity(e).insert(HeightDraft(first.y));
+            } else {
+                commands.entity(e).remove::<Editing>();
+                event_queue.push(DrawShapeEvent::Finished(e));
+            }
+        }
+        return;
+    }
+
+    if let Ok(e) = height_drafts.get_single() {
+        if started || pointer.confirm {
+            commands
+                .entity(e)
+                .remove::<Editing>()
+                .remove::<HeightDraft>();
+            event_queue.push(DrawShapeEvent::Finished(e));
         }
+        return;
     }
-    let intersect_position = get_closest_intersection(query);
 
     if started {
         // only do something if we actually have an intersection position
         if let Some(intersect_position) = intersect_position {
-            let mut transform = Transform::default();
-            transform.translation = intersect_position
-                + Vec3::new(
-                    resources.initial_size / 2.,
-                    height / 2.,
-                    resources.initial_size / 2.,
-                );
+            let (shape, offset) = match mode {
+                DrawMode::Box => (
+                    Shape::Box(Vec3::new(initial_size, height, initial_size)),
+                    Vec3::new(initial_size / 2., height / 2., initial_size / 2.),
+                ),
+                DrawMode::Cylinder => (
+                    Shape::Cylinder {
+                        radius: initial_size / 2.,
+                        height,
+                    },
+                    Vec3::new(0., height / 2., 0.),
+                ),
+                DrawMode::Prism => (
+                    Shape::Prism {
+                        points: vec![Vec2::ZERO, Vec2::ZERO],
+                        height,
+                    },
+                    Vec3::new(0., height / 2., 0.),
+                ),
+                DrawMode::OrientedBox => (
+                    Shape::Box(Vec3::new(initial_size, height, initial_size)),
+                    Vec3::new(0., height / 2., 0.),
+                ),
+            };
+            let transform = Transform::from_translation(intersect_position + offset);
             let origin: Vec3 = intersect_position;
 
-            let mesh = meshes.add(Mesh::from(shape::Box::new(
-                resources.initial_size,
-                height,
-                resources.initial_size,
-            )));
-
             let new_drawing = redraw.is_none();
+            let original = redraw
+                .and_then(|e| shapes.get(e).ok())
+                .map(|(shape, transform)| ShapeSnapshot {
+                    shape: shape.clone(),
+                    transform: *transform,
+                });
 
             let mut e_commands = match redraw {
                 Some(e) => commands.entity(e),
-                None => commands.spawn(PbrBundle {
-                    mesh: mesh.clone(),
-                    material: resources.material.clone(),
-                    transform,
-                    ..Default::default()
-                }),
+                None => {
+                    let mesh = meshes.add(shape.mesh());
+                    let mut e_commands = commands.spawn(shape_bundle(
+                        shape.clone(),
+                        transform,
+                        mesh,
+                        resources.material.clone(),
+                    ));
+                    e_commands.insert(style.0.clone());
+                    e_commands
+                }
             };
 
             let e = e_commands
-                .insert(Editing(origin))
-                .insert(Shape::Box(Vec3::new(
-                    resources.initial_size,
-                    height,
-                    resources.initial_size,
-                )))
+                .insert(Editing {
+                    origin,
+                    original,
+                    from_center: resources.from_center || pointer.from_center,
+                })
+                .insert(shape)
                 .id();
 
+            if let DrawMode::Prism | DrawMode::OrientedBox = mode {
+                commands.entity(e).insert(PointDraft {
+                    points: vec![origin],
+                    last_click: time.elapsed_seconds_f64(),
+                });
+            }
+
             if new_drawing {
                 event_queue.push(DrawShapeEvent::Spawned(e));
             } else {
                 event_queue.push(DrawShapeEvent::Redrawing(e));
             }
         }
-    } else if ended {
-        if let Ok(e) = edit_box.get_single() {
-            commands.entity(e).remove::<Editing>();
-            event_queue.push(DrawShapeEvent::Finished(e));
+    } else if ended || pointer.confirm {
+        if let Ok((e, transform, shape, edit, handle)) = edit_box.get_single() {
+            if too_small(shape, &resources, &units) {
+                revert(e, edit, handle, &mut meshes, &mut commands);
+                event_queue.push(DrawShapeEvent::Discarded(e));
+            } else if resources.height_phase {
+                let base = transform.translation.y - shape.height() / 2.;
+                commands.entity(e).insert(HeightDraft(base));
+            } else {
+                commands.entity(e).remove::<Editing>();
+                event_queue.push(DrawShapeEvent::Finished(e));
+            }
         }
     }
 }
 
-fn get_closest_intersection<'a>(
-    query: Query<&'a Intersection<ShapeDrawRaycastSet>>,
-) -> Option<Vec3> {
-    let mut intersect_position = None;
-    // large value, we will only pick the closest pick-source in the case of multiple pick-sources
-    let mut distance = f32::INFINITY;
-    for intersection in &query {
-        debug!(
-            "Distance {:?}, Position {:?}",
-            intersection.distance(),
-            intersection.position()
-        );
-        //
-        if let (Some(dist), Some(pos)) = (intersection.distance(), intersection.position()) {
-            if dist < distance {
-                distance = dist;
-                intersect_position = Some(*pos);
+#[allow(clippy::type_complexity)]
+pub(crate) fn edit_box(
+    mut e_box: Query<
+        (
+            &Handle<Mesh>,
+            &mut Transform,
+            &Editing,
+            &mut Shape,
+            Option<&PointDraft>,
+        ),
+        Without<HeightDraft>,
+    >,
+    pointer: Res<PointerInput>,
+    resources: Res<BoxDrawResources>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    state: Res<DrawingState>,
+    units: Res<ShapeUnits>,
+) {
+    if let DrawingState::Disabled = *state {
+        return;
+    }
+
+    // Shapes drawn point by point follow the pointer between clicks, not only while pressed.
+    // The pointer can also be held with nothing to edit, after a cancel or a committed height
+    let update = pointer.pressed || e_box.iter().any(|(.., draft)| draft.is_some());
+
+    if update {
+        if let Ok((handle, mut transform, edit_origin, mut shape, draft)) = e_box.get_single_mut() {
+            let from_center = edit_origin.from_center;
+            if let Some(mesh) = meshes.get_mut(handle) {
+                let opposite = match pointer.position {
+                    Some(position) if position != edit_origin.origin => position,
+                    _ => return,
+                };
+
+                let p1 = edit_origin.origin;
+                let p2 = opposite;
+
+                let mut dx = p2.x - p1.x;
+                let mut dz = p2.z - p1.z;
+
+                let height = shape.height();
+
+                match &mut *shape {
+                    Shape::Box(size) => match draft {
+                        Some(draft) => {
+                            // Until the edge is clicked the pointer is its end, then the width
+                            let (end, width) = match draft.points.get(1) {
+                                Some(end) if pointer.square => {
+                                    (*end, square_width(p1, *end, edge_offset(p1, *end, p2)))
+                                }
+                                Some(end) => (*end, edge_offset(p1, *end, p2)),
+                                None if pointer.axis_lock => {
+                                    (lock_to_axis(p1, p2), units.to_world(resources.initial_size))
+                                }
+                                None => (p2, units.to_world(resources.initial_size)),
+                            };
+                            let Some((footprint, rotation, center)) =
+                                oriented_box(p1, end, width, from_center)
+                            else {
+                                return;
+                            };
+                            size.x = footprint.x;
+                            size.z = footprint.y;
+                            transform.translation = center;
+                            transform.rotation = rotation;
+                        }
+                        None => {
+                            // From the center the pointer sets half of the size
+                            let scale = if from_center { 2. } else { 1. };
+                            if pointer.axis_lock {
+                                // The other axis keeps the size it had when the lock started
+                                let kept = match from_center {
+                                    true => Vec3::new(size.x, 0., size.z) / 2.,
+                                    false => (transform.translation - p1) * 2.,
+                                };
+                                if dx.abs() >= dz.abs() {
+                                    dz = kept.z;
+                                } else {
+                                    dx = kept.x;
+                                }
+                            }
+                            if pointer.square {
+                                let side = dx.abs().max(dz.abs());
+                                dx = side.copysign(dx);
+                                dz = side.copysign(dz);
+                            }
+                            size.x = dx.abs() * scale;
+                            size.z = dz.abs() * scale;
+                            let center = match from_center {
+                                true => p1,
+                                false => p1 + Vec3::new(dx, 0., dz) / 2.,
+                            };
+                            transform.translation.x = center.x;
+                            transform.translation.z = center.z;
+                            transform.rotation = Quat::IDENTITY;
+                        }
+                    },
+                    Shape::Cylinder { radius, .. } => {
+                        let r = Vec3::new(dx, 0., dz).length();
+                        if r <= 0. {
+                            return;
+                        }
+                        *radius = r;
+                        transform.translation.x = p1.x;
+                        transform.translation.z = p1.z;
+                        transform.rotation = Quat::IDENTITY;
+                    }
+                    Shape::Prism { points, .. } => {
+                        if let Some(draft) = draft {
+                            let last = draft.points.last().copied().unwrap_or(p1);
+                            let p2 = match pointer.axis_lock {
+                                true => lock_to_axis(last, p2),
+                                false => p2,
+                            };
+                            *points = draft
+                                .points
+                                .iter()
+                                .chain([&p2])
+                                .map(|p| Vec2::new(p.x - p1.x, p.z - p1.z))
+                                .collect();
+                        }
+                        transform.translation = p1;
+                        transform.rotation = Quat::IDENTITY;
+                    }
+                }
+
+                debug!("Shape: {:?}", shape);
+
+                *mesh = shape.mesh();
+                transform.translation.y = match draft {
+                    // Shapes drawn point by point stay at the height of the first point
+                    Some(_) => p1.y + (height / 2.0),
+                    None => opposite.y + (height / 2.0),
+                };
             }
         }
     }
-    intersect_position
 }
 
-pub(crate) fn edit_box(
-    mut e_box: Query<(&Handle<Mesh>, &mut Transform, &Editing, &mut Shape)>,
-    query: Query<&Intersection<ShapeDrawRaycastSet>>,
-    keys: Res<Input<MouseButton>>,
+/// Stops drawing the current shape without finishing it
+pub(crate) fn cancel_drawing(
+    pointer: Res<PointerInput>,
+    editing: Query<(Entity, &Editing, &Handle<Mesh>)>,
     mut meshes: ResMut<Assets<Mesh>>,
-    state: Res<DrawingState>,
-    mut touch_events: EventReader<TouchInput>,
-    touch_id: Res<TouchId>,
+    mut commands: Commands,
+    mut event_queue: ResMut<DrawEventQueue>,
 ) {
-    match *state {
-        DrawingState::Disabled => return,
-        _ => {}
+    if !pointer.cancel {
+        return;
     }
 
-    let mut update = keys.pressed(MouseButton::Left);
+    for (e, edit, handle) in &editing {
+        revert(e, edit, handle, &mut meshes, &mut commands);
+        event_queue.push(DrawShapeEvent::Cancelled(e));
+    }
+}
 
-    for ev in touch_events.iter() {
-        if let Some(id) = touch_id.0 {
-            if id != ev.id {
-                warn!("Wrong touch id");
-                continue;
+/// Despawns a new shape or puts back the original geometry of a redrawn one
+fn revert(
+    e: Entity,
+    edit: &Editing,
+    handle: &Handle<Mesh>,
+    meshes: &mut Assets<Mesh>,
+    commands: &mut Commands,
+) {
+    match &edit.original {
+        Some(original) => {
+            if let Some(mesh) = meshes.get_mut(handle) {
+                *mesh = original.shape.mesh();
             }
+            commands
+                .entity(e)
+                .remove::<Editing>()
+                .remove::<PointDraft>()
+                .remove::<HeightDraft>()
+                .insert(original.shape.clone())
+                .insert(original.transform);
         }
+        None => commands.entity(e).despawn(),
+    }
+}
+
+fn too_small(shape: &Shape, resources: &BoxDrawResources, units: &ShapeUnits) -> bool {
+    let footprint = shape.footprint() / units.scale();
+    footprint.x < resources.min_size.x || footprint.y < resources.min_size.y
+}
 
-        update = true;
-        break;
+/// Moves `point` onto the line through `from` along the world X or Z axis,
+/// whichever `point` is furthest along
+fn lock_to_axis(from: Vec3, point: Vec3) -> Vec3 {
+    if (point.x - from.x).abs() >= (point.z - from.z).abs() {
+        Vec3::new(point.x, point.y, from.z)
+    } else {
+        Vec3::new(from.x, point.y, point.z)
     }
+}
 
-    if update {
-        if let Ok((handle, mut transform, edit_origin, mut shape)) = e_box.get_single_mut() {
-            if let Some(mesh) = meshes.get_mut(handle) {
-                let mut opposite = Vec3::default();
+/// The width that makes a box with an edge from `a` to `b` square, on the same side as `width`
+fn square_width(a: Vec3, b: Vec3, width: f32) -> f32 {
+    Vec2::new(b.x - a.x, b.z - a.z).length().copysign(width)
+}
 
-                for intersection in &query {
-                    if let Some(pos) = intersection.position() {
-                        opposite = *pos;
-                    }
-                }
+/// The signed distance from `c` to the line through `a` and `b` in the XZ plane
+fn edge_offset(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let edge = Vec2::new(b.x - a.x, b.z - a.z).normalize_or_zero();
+    edge.perp().dot(Vec2::new(c.x - a.x, c.z - a.z))
+}
 
-                if opposite == Vec3::ZERO || opposite == edit_origin.0 {
-                    return;
-                }
+/// Returns the footprint size, rotation and center of a box with one edge from `a` to `b`,
+/// which extends `width` to the side of the edge given by [`edge_offset`].
+/// From the center, `a` is the center of the box and `b` the middle of one side instead
+fn oriented_box(a: Vec3, b: Vec3, width: f32, from_center: bool) -> Option<(Vec2, Quat, Vec3)> {
+    let edge = Vec2::new(b.x - a.x, b.z - a.z);
+    let length = edge.length();
+    if length <= f32::EPSILON {
+        return None;
+    }
 
-                let p1 = edit_origin.0;
-                let p2 = opposite;
+    let direction = edge / length;
+    // Rotates the local X axis onto the edge
+    let rotation = Quat::from_rotation_y(f32::atan2(-direction.y, direction.x));
+    if from_center {
+        return Some((Vec2::new(length, width.abs()) * 2., rotation, a));
+    }
 
-                let dx = p2.x - p1.x;
-                let dz = p2.z - p1.z;
+    let middle = Vec2::new(a.x, a.z) + edge / 2. + direction.perp() * width / 2.;
+    Some((
+        Vec2::new(length, width.abs()),
+        rotation,
+        Vec3::new(middle.x, a.y, middle.y),
+    ))
+}
 
-                let x = dx.abs();
-                let z = dz.abs();
+/// Sets the height of the shape to where the pointer ray hits a vertical plane through the
+/// shape that faces the camera
+pub(crate) fn edit_height(
+    mut e_height: Query<(&Handle<Mesh>, &mut Transform, &mut Shape, &HeightDraft)>,
+    sources: Query<&DrawShapeRaycastSource>,
+    resources: Res<BoxDrawResources>,
+    units: Res<ShapeUnits>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((handle, mut transform, mut shape, base)) = e_height.get_single_mut() else {
+        return;
+    };
+    let Some(ray) = sources.iter().find_map(|source| source.get_ray()) else {
+        return;
+    };
 
-                let height = match &mut *shape {
-                    Shape::Box(size) => {
-                        size.x = x;
-                        size.z = z;
-                        size.y
-                    }
-                };
+    let normal = -Vec3::new(ray.direction().x, 0., ray.direction().z).normalize_or_zero();
+    if normal == Vec3::ZERO {
+        return;
+    }
 
-                let b = shape::Box::new(x, height, z);
+    let plane = Primitive3d::Plane {
+        point: transform.translation,
+        normal,
+    };
+    let Some(hit) = ray.intersects_primitive(plane) else {
+        return;
+    };
+    if hit.distance() < 0. {
+        return;
+    }
 
-                debug!("Box: {:?}", b);
+    let height = (hit.position().y - base.0).max(units.to_world(resources.initial_size));
+    if height == shape.height() {
+        return;
+    }
 
-                *mesh = Mesh::from(b);
-                transform.translation.x = p2.x - (dx / 2.0);
-                transform.translation.z = p2.z - (dz / 2.0);
-                transform.translation.y = opposite.y + (height / 2.0);
-            }
-        } else {
-            /* TODO: There is currently a bug that when you are in the browser and are using the Device toolbar for touch.
-            If you spam enough boxes it will eventually fall into a state that only returns the warning below */
-            warn!("No editbox found");
-        }
+    shape.set_height(height);
+    if let Some(mesh) = meshes.get_mut(handle) {
+        *mesh = shape.mesh();
     }
+    transform.translation.y = base.0 + height / 2.;
 }
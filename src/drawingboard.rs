@@ -1,7 +1,7 @@
 use bevy::prelude::{
-    info, shape, AlphaMode, Assets, Camera, Commands, Component, Entity, EventReader, FromWorld,
-    GlobalTransform, Handle, Mesh, PbrBundle, Query, Res, Resource, StandardMaterial, Transform,
-    With, World,
+    info, shape, Added, AlphaMode, Assets, Camera, Commands, Component, Entity, EventReader,
+    FromWorld, GlobalTransform, Handle, Mesh, PbrBundle, Query, Reflect, ReflectComponent, Res,
+    Resource, StandardMaterial, Transform, With, Without, World,
 };
 
 use crate::DrawShapeRaycastMesh;
@@ -47,9 +47,30 @@ impl FromWorld for DrawingboardResource {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Drawingboard;
 
+/// A drawingboard spawned from a scene gets its mesh back
+#[allow(clippy::type_complexity)]
+pub(crate) fn restore_drawingboard(
+    resource: Res<DrawingboardResource>,
+    mut commands: Commands,
+    drawingboard: Query<(Entity, &Transform), (Added<Drawingboard>, Without<Handle<Mesh>>)>,
+) {
+    for (e, transform) in &drawingboard {
+        commands
+            .entity(e)
+            .insert(PbrBundle {
+                transform: *transform,
+                mesh: resource.mesh.clone(),
+                material: resource.material.clone(),
+                ..Default::default()
+            })
+            .insert(DrawShapeRaycastMesh::default());
+    }
+}
+
 pub(crate) fn spawn_drawingboard(
     resource: Res<DrawingboardResource>,
     mut commands: Commands,
@@ -63,7 +84,7 @@ pub(crate) fn spawn_drawingboard(
                 if drawingboard.iter().len() > 0 {
                     continue;
                 }
-                for transform in camera.iter() {
+                if let Some(transform) = camera.iter().next() {
                     let transform = Transform::from_xyz(
                         transform.translation().x,
                         *y,
@@ -81,7 +102,6 @@ pub(crate) fn spawn_drawingboard(
                         })
                         .insert(Drawingboard)
                         .insert(DrawShapeRaycastMesh::default());
-                    break;
                 }
             }
             DrawingboardEvent::Despawn => {
@@ -0,0 +1,324 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::{error, Entity, EventReader, GlobalTransform, Mesh, Name, Parent, Query, Vec3},
+    render::mesh::VertexAttributeValues,
+};
+
+use crate::{draw::Editing, Shape};
+
+/// The file formats drawn shapes can be exported to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Wavefront OBJ, with every shape as a named object
+    Obj,
+    /// ASCII STL, with every shape as a named solid
+    StlAscii,
+    /// Binary STL, which has no names so every shape ends up in the same solid
+    StlBinary,
+}
+
+/// Exports every drawn shape in world space to the file.
+/// Shapes are named by their [`Name`] if they have one, otherwise by their entity
+pub struct ExportShapes(pub PathBuf, pub ExportFormat);
+
+/// The triangles of a single shape in world space
+struct ExportObject {
+    name: String,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>,
+}
+
+/// Shapes in world space, collected to be written to a file
+#[derive(Default)]
+pub struct ShapeExport {
+    objects: Vec<ExportObject>,
+}
+
+impl ShapeExport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the mesh of the shape with the transform applied
+    pub fn add(&mut self, name: impl Into<String>, shape: &Shape, transform: &GlobalTransform) {
+        let mesh = shape.mesh();
+        let matrix = transform.compute_matrix();
+        let normal_matrix = matrix.inverse().transpose();
+
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions
+                .iter()
+                .map(|p| matrix.transform_point3(Vec3::from(*p)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => normals
+                .iter()
+                .map(|n| {
+                    normal_matrix
+                        .transform_vector3(Vec3::from(*n))
+                        .normalize_or_zero()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let indices = mesh.indices().map_or(Vec::new(), |indices| {
+            indices.iter().map(|i| i as u32).collect()
+        });
+
+        self.objects.push(ExportObject {
+            name: name.into(),
+            positions,
+            normals,
+            indices,
+        });
+    }
+
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        // Indices in OBJ are counted from 1 over the whole file
+        let mut offset = 1;
+        for object in &self.objects {
+            let _ = writeln!(obj, "o {}", object.name);
+            for p in &object.positions {
+                let _ = writeln!(obj, "v {} {} {}", p.x, p.y, p.z);
+            }
+            for n in &object.normals {
+                let _ = writeln!(obj, "vn {} {} {}", n.x, n.y, n.z);
+            }
+            let has_normals = object.normals.len() == object.positions.len();
+            for triangle in object.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize + offset);
+                if has_normals {
+                    let _ = writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}");
+                } else {
+                    let _ = writeln!(obj, "f {a} {b} {c}");
+                }
+            }
+            offset += object.positions.len();
+        }
+        obj
+    }
+
+    pub fn to_stl_ascii(&self) -> String {
+        let mut stl = String::new();
+        for object in &self.objects {
+            let name = object.name.replace(char::is_whitespace, "_");
+            let _ = writeln!(stl, "solid {name}");
+            for [a, b, c] in object.triangles() {
+                let n = facet_normal(a, b, c);
+                let _ = writeln!(stl, "  facet normal {} {} {}", n.x, n.y, n.z);
+                let _ = writeln!(stl, "    outer loop");
+                for p in [a, b, c] {
+                    let _ = writeln!(stl, "      vertex {} {} {}", p.x, p.y, p.z);
+                }
+                let _ = writeln!(stl, "    endloop");
+                let _ = writeln!(stl, "  endfacet");
+            }
+            let _ = writeln!(stl, "endsolid {name}");
+        }
+        stl
+    }
+
+    pub fn to_stl_binary(&self) -> Vec<u8> {
+        let triangles = self
+            .objects
+            .iter()
+            .flat_map(ExportObject::triangles)
+            .collect::<Vec<_>>();
+
+        let mut stl = Vec::with_capacity(84 + triangles.len() * 50);
+        let mut header = [0u8; 80];
+        let title = b"bevy_shape_draw";
+        header[..title.len()].copy_from_slice(title);
+        stl.extend_from_slice(&header);
+        stl.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for [a, b, c] in triangles {
+            for v in [facet_normal(a, b, c), a, b, c] {
+                for f in v.to_array() {
+                    stl.extend_from_slice(&f.to_le_bytes());
+                }
+            }
+            // Attribute byte count
+            stl.extend_from_slice(&[0, 0]);
+        }
+        stl
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: ExportFormat) -> io::Result<()> {
+        match format {
+            ExportFormat::Obj => fs::write(path, self.to_obj()),
+            ExportFormat::StlAscii => fs::write(path, self.to_stl_ascii()),
+            ExportFormat::StlBinary => fs::write(path, self.to_stl_binary()),
+        }
+    }
+}
+
+impl ExportObject {
+    fn triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|triangle| {
+            [0, 1, 2].map(|i| {
+                self.positions
+                    .get(triangle[i] as usize)
+                    .copied()
+                    .unwrap_or_default()
+            })
+        })
+    }
+}
+
+fn facet_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    (b - a).cross(c - a).normalize_or_zero()
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn export_shapes(
+    mut event_reader: EventReader<ExportShapes>,
+    shapes: Query<(
+        Entity,
+        &Shape,
+        &GlobalTransform,
+        Option<&Name>,
+        Option<&Editing>,
+        Option<&Parent>,
+    )>,
+    globals: Query<&GlobalTransform>,
+) {
+    for ExportShapes(path, format) in event_reader.iter() {
+        let mut export = ShapeExport::new();
+        for (e, shape, transform, name, edit, parent) in &shapes {
+            let name = name.map_or_else(|| format!("Shape{}", e.index()), |name| name.to_string());
+            match edit {
+                // Shapes being drawn are exported the way they were before the drawing started.
+                // The original transform is local, so it is placed in the parent like the shape
+                Some(edit) => {
+                    if let Some(original) = &edit.original {
+                        let parent = parent
+                            .and_then(|parent| globals.get(parent.get()).ok())
+                            .copied()
+                            .unwrap_or_default();
+                        export.add(
+                            name,
+                            &original.shape,
+                            &parent.mul_transform(original.transform),
+                        );
+                    }
+                }
+                None => export.add(name, shape, transform),
+            }
+        }
+
+        if let Err(e) = export.save(path, *format) {
+            error!("Failed to export shapes to {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Transform, Vec2};
+
+    use super::*;
+
+    /// A box at the origin and a triangular prism far along the X axis
+    fn export() -> (ShapeExport, [usize; 2], usize) {
+        let shapes = [
+            (Shape::Box(Vec3::ONE), Transform::IDENTITY),
+            (
+                Shape::Prism {
+                    points: vec![Vec2::ZERO, Vec2::X, Vec2::Y],
+                    height: 1.,
+                },
+                Transform::from_xyz(10., 0., 0.),
+            ),
+        ];
+        let mut export = ShapeExport::new();
+        let mut vertices = [0; 2];
+        let mut triangles = 0;
+        for (i, (shape, transform)) in shapes.iter().enumerate() {
+            let mesh = shape.mesh();
+            vertices[i] = mesh.count_vertices();
+            triangles += mesh.indices().unwrap().len() / 3;
+            export.add(
+                format!("Shape{i}"),
+                shape,
+                &GlobalTransform::from(*transform),
+            );
+        }
+        (export, vertices, triangles)
+    }
+
+    #[test]
+    fn obj_indices_continue_across_objects() {
+        let (export, [first, second], triangles) = export();
+        let obj = export.to_obj();
+
+        let positions = obj
+            .lines()
+            .filter_map(|line| line.strip_prefix("v "))
+            .map(|line| {
+                let v = line
+                    .split(' ')
+                    .map(|f| f.parse::<f32>().unwrap())
+                    .collect::<Vec<_>>();
+                Vec3::new(v[0], v[1], v[2])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions.len(), first + second);
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("vn ")).count(),
+            first + second
+        );
+
+        let mut object = None;
+        let mut faces = 0;
+        for line in obj.lines() {
+            if let Some(name) = line.strip_prefix("o ") {
+                object = Some(name.to_string());
+            }
+            let Some(face) = line.strip_prefix("f ") else {
+                continue;
+            };
+            faces += 1;
+            for vertex in face.split(' ') {
+                let (v, n) = vertex.split_once("//").unwrap();
+                assert_eq!(v, n);
+                let index = v.parse::<usize>().unwrap();
+                // Counted from 1, and the second object only uses its own vertices
+                match object.as_deref() {
+                    Some("Shape0") => assert!((1..=first).contains(&index)),
+                    Some("Shape1") => {
+                        assert!((first + 1..=first + second).contains(&index));
+                        assert!(positions[index - 1].x >= 10.);
+                    }
+                    _ => panic!("a face outside of an object"),
+                }
+            }
+        }
+        assert_eq!(faces, triangles);
+    }
+
+    #[test]
+    fn binary_stl_layout() {
+        let (export, _, triangles) = export();
+        let stl = export.to_stl_binary();
+
+        assert_eq!(stl.len(), 84 + 50 * triangles);
+        assert!(stl[..80].starts_with(b"bevy_shape_draw"));
+        let count = u32::from_le_bytes(stl[80..84].try_into().unwrap());
+        assert_eq!(count as usize, triangles);
+
+        // The last triangle belongs to the prism
+        let last = &stl[stl.len() - 50..];
+        let x = f32::from_le_bytes(last[12..16].try_into().unwrap());
+        assert!(x >= 10.);
+        assert_eq!(&last[48..], &[0, 0]);
+    }
+}
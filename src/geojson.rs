@@ -0,0 +1,495 @@
+use std::{fmt, fs, io, path::PathBuf};
+
+use bevy::{
+    math::DVec2,
+    prelude::{
+        error, Assets, Commands, EventReader, EventWriter, Mesh, Quat, Query, Res, ResMut,
+        Resource, Transform, Vec2, Vec3,
+    },
+    utils::HashMap,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    draw::Editing, style::ShapeAppearance, BoxDrawResources, DrawShapeEvent, Shape, ShapeHistory,
+    ShapeMetadata, ShapeSnapshot, ShapeUnits,
+};
+
+/// Writes the footprint of every drawn shape to the file as a GeoJSON feature collection
+pub struct ExportGeoJson(pub PathBuf);
+
+/// Spawns a shape for every polygon in the GeoJSON file, see [`shapes_from_geojson`]
+pub struct ImportGeoJson(pub PathBuf);
+
+/// How world coordinates relate to the projected coordinates used in GeoJSON.
+/// The world X axis points east and the world -Z axis points north before the rotation
+#[derive(Resource, Clone, Debug)]
+pub struct GeoTransform {
+    /// The projected coordinates of the world origin
+    pub origin: DVec2,
+    /// Projected units per world unit, used for both the footprint and the height
+    pub scale: f64,
+    /// Counter-clockwise rotation in radians from the world axes to the projected axes
+    pub rotation: f64,
+}
+
+impl Default for GeoTransform {
+    fn default() -> Self {
+        Self {
+            origin: DVec2::ZERO,
+            scale: 1.,
+            rotation: 0.,
+        }
+    }
+}
+
+impl GeoTransform {
+    /// Projects a point on the ground, ignoring its height
+    pub fn to_geo(&self, world: Vec3) -> DVec2 {
+        let local = DVec2::new(world.x as f64, -world.z as f64);
+        self.origin + DVec2::from_angle(self.rotation).rotate(local) * self.scale
+    }
+
+    /// The world X and Z coordinates of a projected point
+    pub fn to_world(&self, geo: DVec2) -> Vec2 {
+        let local = DVec2::from_angle(-self.rotation).rotate((geo - self.origin) / self.scale);
+        Vec2::new(local.x as f32, -local.y as f32)
+    }
+}
+
+#[derive(Debug)]
+pub enum GeoJsonError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The file is valid JSON but not GeoJSON that shapes can be made of
+    Invalid(&'static str),
+}
+
+impl fmt::Display for GeoJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoJsonError::Io(e) => write!(f, "could not access the GeoJSON file: {e}"),
+            GeoJsonError::Json(e) => write!(f, "invalid JSON: {e}"),
+            GeoJsonError::Invalid(e) => write!(f, "invalid GeoJSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GeoJsonError {}
+
+impl From<io::Error> for GeoJsonError {
+    fn from(e: io::Error) -> Self {
+        GeoJsonError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        GeoJsonError::Json(e)
+    }
+}
+
+/// Properties written for every shape, which are not read back as [`ShapeMetadata`]
+const KIND: &str = "shape";
+const HEIGHT: &str = "height";
+const BASE: &str = "base";
+
+/// A feature with the footprint of the shape as a polygon.
+/// The kind, height and base height of the shape are stored as properties,
+/// together with the metadata. Heights are scaled by [`GeoTransform::scale`] like the footprint
+pub fn shape_to_feature(
+    shape: &Shape,
+    transform: &Transform,
+    metadata: Option<&ShapeMetadata>,
+    geo: &GeoTransform,
+) -> Value {
+    let mut ring = shape
+        .world_outline(transform)
+        .into_iter()
+        .map(|p| geo.to_geo(p))
+        .collect::<Vec<_>>();
+    // The exterior ring of a GeoJSON polygon is counter-clockwise
+    let area: f64 = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    if area < 0. {
+        ring.reverse();
+    }
+    if let Some(first) = ring.first().copied() {
+        ring.push(first);
+    }
+
+    let mut properties = Map::new();
+    if let Some(metadata) = metadata {
+        for (key, value) in &metadata.0 {
+            properties.insert(key.clone(), Value::String(value.clone()));
+        }
+    }
+    let kind = match shape {
+        Shape::Box(_) => "box",
+        Shape::Cylinder { .. } => "cylinder",
+        Shape::Prism { .. } => "prism",
+    };
+    properties.insert(KIND.into(), kind.into());
+    let height = shape.height() as f64;
+    let base = transform.translation.y as f64 - height / 2.;
+    properties.insert(HEIGHT.into(), json!(height * geo.scale));
+    properties.insert(BASE.into(), json!(base * geo.scale));
+
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [ring.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>()],
+        },
+        "properties": properties,
+    })
+}
+
+/// A shape for every polygon in a GeoJSON feature collection, feature or geometry.
+/// Polygons exported from a box or a cylinder become one again as long as their outline
+/// still fits it, and every other polygon becomes a [`Shape::Prism`]. Holes are ignored, and shapes without a height property get the default height in world units.
+/// Height properties are in projected units and divided by [`GeoTransform::scale`]
+pub fn shapes_from_geojson(
+    geojson: &Value,
+    geo: &GeoTransform,
+    default_height: f32,
+) -> Result<Vec<(Shape, Transform, ShapeMetadata)>, GeoJsonError> {
+    let mut shapes = Vec::new();
+    match geojson["type"].as_str() {
+        Some("FeatureCollection") => {
+            let features = geojson["features"].as_array().ok_or(GeoJsonError::Invalid(
+                "a feature collection without features",
+            ))?;
+            for feature in features {
+                feature_shapes(feature, geo, default_height, &mut shapes)?;
+            }
+        }
+        Some("Feature") => feature_shapes(geojson, geo, default_height, &mut shapes)?,
+        Some(_) => geometry_shapes(geojson, &Map::new(), geo, default_height, &mut shapes)?,
+        None => return Err(GeoJsonError::Invalid("an object without a type")),
+    }
+    Ok(shapes)
+}
+
+fn feature_shapes(
+    feature: &Value,
+    geo: &GeoTransform,
+    default_height: f32,
+    shapes: &mut Vec<(Shape, Transform, ShapeMetadata)>,
+) -> Result<(), GeoJsonError> {
+    let properties = feature["properties"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    geometry_shapes(
+        &feature["geometry"],
+        &properties,
+        geo,
+        default_height,
+        shapes,
+    )
+}
+
+fn geometry_shapes(
+    geometry: &Value,
+    properties: &Map<String, Value>,
+    geo: &GeoTransform,
+    default_height: f32,
+    shapes: &mut Vec<(Shape, Transform, ShapeMetadata)>,
+) -> Result<(), GeoJsonError> {
+    let polygons = match geometry["type"].as_str() {
+        Some("Polygon") => vec![&geometry["coordinates"]],
+        Some("MultiPolygon") => geometry["coordinates"]
+            .as_array()
+            .ok_or(GeoJsonError::Invalid("a multi polygon without coordinates"))?
+            .iter()
+            .collect(),
+        // Points and lines have no footprint
+        _ => return Ok(()),
+    };
+
+    let kind = properties.get(KIND).and_then(Value::as_str);
+    let height = properties
+        .get(HEIGHT)
+        .and_then(Value::as_f64)
+        .map_or(default_height, |height| (height / geo.scale) as f32);
+    let base = (properties
+        .get(BASE)
+        .and_then(Value::as_f64)
+        .unwrap_or_default()
+        / geo.scale) as f32;
+    let metadata = ShapeMetadata(
+        properties
+            .iter()
+            .filter(|(key, _)| ![KIND, HEIGHT, BASE].contains(&key.as_str()))
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect::<HashMap<_, _>>(),
+    );
+
+    for polygon in polygons {
+        let exterior = polygon[0]
+            .as_array()
+            .ok_or(GeoJsonError::Invalid("a polygon without an exterior ring"))?;
+        let mut points = exterior
+            .iter()
+            .map(
+                |position| match (position[0].as_f64(), position[1].as_f64()) {
+                    (Some(x), Some(y)) => Ok(geo.to_world(DVec2::new(x, y))),
+                    _ => Err(GeoJsonError::Invalid(
+                        "a position that is not a number pair",
+                    )),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        // The ring is closed by repeating the first position
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 3 {
+            continue;
+        }
+
+        if let Some((shape, mut transform)) =
+            kind.and_then(|kind| exported_shape(kind, &points, height))
+        {
+            transform.translation.y = base + height / 2.;
+            shapes.push((shape, transform, metadata.clone()));
+            continue;
+        }
+
+        // Center the footprint around the entity, like a drawn prism
+        let min = points
+            .iter()
+            .fold(Vec2::splat(f32::INFINITY), |a, b| a.min(*b));
+        let max = points
+            .iter()
+            .fold(Vec2::splat(f32::NEG_INFINITY), |a, b| a.max(*b));
+        let center = (min + max) / 2.;
+        let points = points.into_iter().map(|p| p - center).collect();
+
+        shapes.push((
+            Shape::Prism { points, height },
+            Transform::from_xyz(center.x, base + height / 2., center.y),
+            metadata.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Rebuilds a box or a cylinder from the outline it was exported with, placed on the ground.
+/// Returns `None` for other kinds and for outlines that no longer fit the kind
+fn exported_shape(kind: &str, points: &[Vec2], height: f32) -> Option<(Shape, Transform)> {
+    let center = points.iter().sum::<Vec2>() / points.len() as f32;
+    let (shape, rotation) = match kind {
+        "box" if points.len() == 4 => {
+            let (width, depth) = (points[1] - points[0], points[2] - points[1]);
+            // Rotates the local X axis onto the first edge, like a box drawn from three points
+            let direction = width.normalize_or_zero();
+            let rotation = Quat::from_rotation_y(f32::atan2(-direction.y, direction.x));
+            let size = Vec3::new(width.length(), height, depth.length());
+            (Shape::Box(size), rotation)
+        }
+        "cylinder" => {
+            let radius =
+                points.iter().map(|p| p.distance(center)).sum::<f32>() / points.len() as f32;
+            (Shape::Cylinder { radius, height }, Quat::IDENTITY)
+        }
+        _ => return None,
+    };
+    let transform = Transform::from_xyz(center.x, 0., center.y).with_rotation(rotation);
+
+    let tolerance = 1e-3 * shape.footprint().max_element().max(1.);
+    let fits = match &shape {
+        Shape::Cylinder { radius, .. } => points
+            .iter()
+            .all(|p| (p.distance(center) - radius).abs() < tolerance),
+        _ => {
+            let outline = shape.world_outline(&transform);
+            points.iter().all(|p| {
+                outline
+                    .iter()
+                    .any(|corner| Vec2::new(corner.x, corner.z).distance(*p) < tolerance)
+            })
+        }
+    };
+    fits.then_some((shape, transform))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn geojson_files(
+    mut export_events: EventReader<ExportGeoJson>,
+    mut import_events: EventReader<ImportGeoJson>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut history: ResMut<ShapeHistory>,
+    resources: Res<BoxDrawResources>,
+    units: Res<ShapeUnits>,
+    geo: Option<Res<GeoTransform>>,
+    shapes: Query<(&Shape, &Transform, Option<&ShapeMetadata>, Option<&Editing>)>,
+    mut event_writer: EventWriter<DrawShapeEvent>,
+) {
+    let geo = geo.map_or_else(GeoTransform::default, |geo| geo.clone());
+
+    for ExportGeoJson(path) in export_events.iter() {
+        let features = shapes
+            .iter()
+            .filter_map(|(shape, transform, metadata, edit)| {
+                // Shapes being drawn are exported the way they were before the drawing started
+                let (shape, transform) = match edit {
+                    Some(edit) => {
+                        let original = edit.original.as_ref()?;
+                        (&original.shape, &original.transform)
+                    }
+                    None => (shape, transform),
+                };
+                Some(shape_to_feature(shape, transform, metadata, &geo))
+            })
+            .collect::<Vec<_>>();
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": features,
+        });
+        let result = serde_json::to_string_pretty(&collection)
+            .map_err(GeoJsonError::from)
+            .and_then(|contents| Ok(fs::write(path, contents)?));
+        if let Err(e) = result {
+            error!("Failed to export GeoJSON to {}: {e}", path.display());
+        }
+    }
+
+    for ImportGeoJson(path) in import_events.iter() {
+        let result = fs::read_to_string(path)
+            .map_err(GeoJsonError::from)
+            .and_then(|contents| Ok(serde_json::from_str::<Value>(&contents)?))
+            .and_then(|geojson| {
+                shapes_from_geojson(&geojson, &geo, units.to_world(resources.initial_height))
+            });
+        let imported = match result {
+            Ok(imported) => imported,
+            Err(e) => {
+                error!("Failed to import GeoJSON from {}: {e}", path.display());
+                continue;
+            }
+        };
+
+        for (shape, transform, metadata) in imported {
+            let snapshot = ShapeSnapshot { shape, transform };
+            let appearance = ShapeAppearance {
+                metadata: Some(metadata),
+                ..Default::default()
+            };
+            let e = appearance.spawn(snapshot.clone(), &mut commands, &mut meshes, &resources);
+            history.record_with_appearance(e, None, Some(snapshot), appearance);
+            event_writer.send(DrawShapeEvent::Loaded(e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Quat;
+
+    use super::*;
+
+    fn geo() -> GeoTransform {
+        GeoTransform {
+            origin: DVec2::new(1000., 2000.),
+            scale: 3.28,
+            rotation: 0.7,
+        }
+    }
+
+    #[test]
+    fn projection_round_trip() {
+        let geo = geo();
+        for point in [
+            Vec3::ZERO,
+            Vec3::new(3., 1., -2.),
+            Vec3::new(-10.5, 0., 7.25),
+        ] {
+            let world = geo.to_world(geo.to_geo(point));
+            assert!((world - Vec2::new(point.x, point.z)).length() < 1e-4);
+        }
+        // North is the world -Z axis before the rotation
+        let north = GeoTransform {
+            rotation: 0.,
+            ..geo
+        }
+        .to_geo(Vec3::new(0., 0., -1.));
+        assert!((north - DVec2::new(1000., 2003.28)).length() < 1e-9);
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let geo = geo();
+        let shape = Shape::Box(Vec3::new(4., 3., 2.));
+        let transform = Transform::from_xyz(5., 2.5, -3.).with_rotation(Quat::from_rotation_y(0.4));
+        let metadata = ShapeMetadata(HashMap::from_iter([("zone".into(), "hazard".into())]));
+
+        let feature = shape_to_feature(&shape, &transform, Some(&metadata), &geo);
+        assert!((feature["properties"][HEIGHT].as_f64().unwrap() - 3. * 3.28).abs() < 1e-4);
+        assert!((feature["properties"][BASE].as_f64().unwrap() - 3.28).abs() < 1e-4);
+
+        let collection = json!({ "type": "FeatureCollection", "features": [feature] });
+        let imported = shapes_from_geojson(&collection, &geo, 1.).unwrap();
+        assert_eq!(imported.len(), 1);
+        let (imported_shape, imported_transform, imported_metadata) = &imported[0];
+
+        assert!(matches!(imported_shape, Shape::Box(_)));
+        assert!((imported_shape.height() - 3.).abs() < 1e-4);
+        assert!((imported_transform.translation.y - 2.5).abs() < 1e-4);
+        assert_eq!(imported_metadata, &metadata);
+
+        let outline = imported_shape.world_outline(imported_transform);
+        let original = shape.world_outline(&transform);
+        assert_eq!(outline.len(), original.len());
+        for corner in original {
+            assert!(
+                outline
+                    .iter()
+                    .any(|p| Vec2::new(p.x - corner.x, p.z - corner.z).length() < 1e-3),
+                "{corner} is missing from {outline:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn import_rebuilds_exported_kind() {
+        let geo = geo();
+        let cylinder = Shape::Cylinder {
+            radius: 1.5,
+            height: 2.,
+        };
+        let transform = Transform::from_xyz(-4., 1., 6.);
+        let feature = shape_to_feature(&cylinder, &transform, None, &geo);
+        let imported = shapes_from_geojson(&feature, &geo, 1.).unwrap();
+        match &imported[0] {
+            (Shape::Cylinder { radius, height }, imported_transform, _) => {
+                assert!((radius - 1.5).abs() < 1e-3);
+                assert!((height - 2.).abs() < 1e-4);
+                assert!((imported_transform.translation - transform.translation).length() < 1e-3);
+            }
+            other => panic!("expected a cylinder, got {other:?}"),
+        }
+
+        // A box whose outline was changed elsewhere is kept as it is
+        let mut feature = shape_to_feature(
+            &Shape::Box(Vec3::ONE),
+            &Transform::IDENTITY,
+            None,
+            &GeoTransform::default(),
+        );
+        feature["geometry"]["coordinates"][0][2] = json!([0.9, 0.2]);
+        let imported = shapes_from_geojson(&feature, &GeoTransform::default(), 1.).unwrap();
+        assert!(matches!(imported[0].0, Shape::Prism { .. }));
+    }
+}
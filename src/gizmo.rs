@@ -0,0 +1,402 @@
+use bevy::prelude::{
+    shape, AlphaMode, Assets, Color, Commands, Component, Entity, EulerRot, EventReader,
+    EventWriter, FromWorld, Handle, Mesh, PbrBundle, Quat, Query, Res, ResMut, Resource,
+    StandardMaterial, Transform, Vec2, Vec3, Without, World,
+};
+use bevy_mod_raycast::Primitive3d;
+
+use crate::{
+    input::PointerInput,
+    raycast::{GizmoRaycastMesh, GizmoRaycastSource},
+    BoxDrawResources, DrawShapeEvent, DrawShapeRaycastSource, GridSnapping, Shape, ShapeHistory,
+    ShapeSnapshot, ShapeUnits,
+};
+
+pub enum ShapeGizmoEvent {
+    /// Shows the handles for moving, resizing and rotating the shape,
+    /// replacing any previous gizmo
+    Attach(Entity),
+    Detach,
+}
+
+/// The handle meshes are sized in meters and scaled by
+/// [`ShapeUnits::world_units_per_meter`](crate::ShapeUnits::world_units_per_meter)
+#[derive(Resource)]
+pub struct ShapeGizmoResources {
+    /// Used for the handles that resize a shape
+    pub handle_mesh: Handle<Mesh>,
+    pub handle_material: Handle<StandardMaterial>,
+    /// Used for the handle that moves a shape
+    pub move_mesh: Handle<Mesh>,
+    pub move_material: Handle<StandardMaterial>,
+    /// Used for the handle that rotates a shape around the Y axis
+    pub rotate_material: Handle<StandardMaterial>,
+    /// Rotations are rounded to multiples of this angle in radians
+    pub rotation_snap: Option<f32>,
+}
+
+impl FromWorld for ShapeGizmoResources {
+    fn from_world(world: &mut World) -> Self {
+        let world = world.cell();
+        let mut materials = world
+            .get_resource_mut::<Assets<StandardMaterial>>()
+            .unwrap();
+
+        let handle_material = materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            unlit: true,
+            ..Default::default()
+        });
+        let move_material = materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 0.8, 0.0, 0.8),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..Default::default()
+        });
+
+        let rotate_material = materials.add(StandardMaterial {
+            base_color: Color::rgba(0.0, 0.8, 1.0, 0.8),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..Default::default()
+        });
+
+        let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
+
+        let handle_mesh = meshes.add(Mesh::from(shape::Cube { size: 0.06 }));
+        let move_mesh = meshes.add(Mesh::from(shape::UVSphere {
+            radius: 0.06,
+            ..Default::default()
+        }));
+
+        Self {
+            handle_mesh,
+            handle_material,
+            move_mesh,
+            move_material,
+            rotate_material,
+            rotation_snap: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum HandleKind {
+    Move,
+    /// Rotates around the Y axis, placed outside of the shape
+    Rotate,
+    /// Moves the sides of a box facing the signs of the local X and Z axes,
+    /// a corner moves two sides at once
+    Side(Vec2),
+    Radius,
+    Vertex(usize),
+}
+
+impl HandleKind {
+    /// All handles of a shape
+    fn all(shape: &Shape) -> Vec<HandleKind> {
+        let mut kinds = vec![HandleKind::Move, HandleKind::Rotate];
+        match shape {
+            Shape::Box(_) => {
+                for x in [-1., 0., 1.] {
+                    for z in [-1., 0., 1.] {
+                        if x != 0. || z != 0. {
+                            kinds.push(HandleKind::Side(Vec2::new(x, z)));
+                        }
+                    }
+                }
+            }
+            Shape::Cylinder { .. } => kinds.push(HandleKind::Radius),
+            Shape::Prism { points, .. } => {
+                kinds.extend((0..points.len()).map(HandleKind::Vertex));
+            }
+        }
+        kinds
+    }
+
+    /// Where the handle is in the local space of the shape,
+    /// with `scale` world units per meter for the rotate handle's distance from the shape
+    fn position(&self, shape: &Shape, scale: f32) -> Vec3 {
+        let top = shape.height() / 2.;
+        match (self, shape) {
+            (HandleKind::Side(side), Shape::Box(size)) => {
+                // Corners sit on top while sides sit in the middle of their face
+                let y = if side.x != 0. && side.y != 0. {
+                    top
+                } else {
+                    0.
+                };
+                Vec3::new(side.x * size.x / 2., y, side.y * size.z / 2.)
+            }
+            (HandleKind::Rotate, _) => Vec3::new(0., top, shape.footprint().y / 2. + 0.2 * scale),
+            (HandleKind::Radius, Shape::Cylinder { radius, .. }) => Vec3::new(*radius, 0., 0.),
+            (HandleKind::Vertex(i), Shape::Prism { points, .. }) => points
+                .get(*i)
+                .map_or(Vec3::ZERO, |p| Vec3::new(p.x, top, p.y)),
+            _ => Vec3::new(0., top, 0.),
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct GizmoHandle {
+    target: Entity,
+    kind: HandleKind,
+}
+
+/// The shape that currently has the gizmo
+#[derive(Resource, Default)]
+pub(crate) struct GizmoTarget(pub Option<Entity>);
+
+#[derive(Clone)]
+pub(crate) struct Drag {
+    kind: HandleKind,
+    entity: Entity,
+    before: ShapeSnapshot,
+    /// Where the pointer is relative to the shape's translation when the drag started
+    grab: Vec3,
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct GizmoDrag(Option<Drag>);
+
+pub(crate) fn gizmo_target(
+    mut event_reader: EventReader<ShapeGizmoEvent>,
+    mut target: ResMut<GizmoTarget>,
+) {
+    for ev in event_reader.iter() {
+        match ev {
+            ShapeGizmoEvent::Attach(e) => target.0 = Some(*e),
+            ShapeGizmoEvent::Detach => target.0 = None,
+        }
+    }
+}
+
+/// Spawns the handles for the gizmo target and keeps them on the shape
+pub(crate) fn update_gizmo_handles(
+    mut commands: Commands,
+    mut target: ResMut<GizmoTarget>,
+    shapes: Query<(&Shape, &Transform)>,
+    mut handles: Query<(Entity, &GizmoHandle, &mut Transform), Without<Shape>>,
+    resources: Res<ShapeGizmoResources>,
+    units: Res<ShapeUnits>,
+) {
+    let shape = target.0.and_then(|e| shapes.get(e).ok());
+    if shape.is_none() {
+        target.0 = None;
+    }
+
+    let kinds = shape.map_or(Vec::new(), |(shape, _)| HandleKind::all(shape));
+    let current = handles
+        .iter()
+        .map(|(_, handle, _)| (handle.target, handle.kind))
+        .collect::<Vec<_>>();
+
+    let up_to_date = current.len() == kinds.len()
+        && kinds.iter().all(|kind| {
+            current
+                .iter()
+                .any(|(e, k)| Some(*e) == target.0 && k == kind)
+        });
+
+    if !up_to_date {
+        for (e, ..) in &handles {
+            commands.entity(e).despawn();
+        }
+
+        let (Some(e), Some((shape, transform))) = (target.0, shape) else {
+            return;
+        };
+        for kind in kinds {
+            let (mesh, material) = match kind {
+                HandleKind::Move => (&resources.move_mesh, &resources.move_material),
+                HandleKind::Rotate => (&resources.move_mesh, &resources.rotate_material),
+                _ => (&resources.handle_mesh, &resources.handle_material),
+            };
+            commands.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: place_handle(shape, transform, kind, &units),
+                    ..Default::default()
+                },
+                GizmoHandle { target: e, kind },
+                GizmoRaycastMesh::default(),
+            ));
+        }
+        return;
+    }
+
+    if let Some((shape, transform)) = shape {
+        for (_, handle, mut handle_transform) in &mut handles {
+            *handle_transform = place_handle(shape, transform, handle.kind, &units);
+        }
+    }
+}
+
+/// The handle meshes are sized in meters and scaled to the world
+fn place_handle(
+    shape: &Shape,
+    transform: &Transform,
+    kind: HandleKind,
+    units: &ShapeUnits,
+) -> Transform {
+    let scale = units.from_meters(1.);
+    Transform::from_translation(transform.transform_point(kind.position(shape, scale)))
+        .with_rotation(transform.rotation)
+        .with_scale(Vec3::splat(scale))
+}
+
+/// Starts dragging when a handle is pressed and moves, resizes or rotates the shape until released.
+/// The pointer is captured while dragging so that nothing is drawn at the same time
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn drag_gizmo(
+    mut pointer: ResMut<PointerInput>,
+    mut drag: ResMut<GizmoDrag>,
+    gizmo_sources: Query<&GizmoRaycastSource>,
+    ray_sources: Query<&DrawShapeRaycastSource>,
+    handles: Query<&GizmoHandle>,
+    mut shapes: Query<(&mut Shape, &mut Transform, &Handle<Mesh>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    grid: Option<Res<GridSnapping>>,
+    units: Res<ShapeUnits>,
+    resources: Res<BoxDrawResources>,
+    gizmo_resources: Res<ShapeGizmoResources>,
+    mut history: ResMut<ShapeHistory>,
+    mut event_writer: EventWriter<DrawShapeEvent>,
+) {
+    if drag.0.is_none() && pointer.started {
+        let handle = gizmo_sources
+            .iter()
+            .filter_map(|source| source.get_nearest_intersection())
+            .min_by(|(_, a), (_, b)| a.distance().total_cmp(&b.distance()))
+            .and_then(|(e, _)| handles.get(e).ok());
+
+        if let Some(handle) = handle {
+            if let Ok((shape, transform, _)) = shapes.get(handle.target) {
+                let grab = ground_point(&ray_sources, shape, transform)
+                    .map_or(Vec3::ZERO, |point| point - transform.translation);
+                drag.0 = Some(Drag {
+                    kind: handle.kind,
+                    entity: handle.target,
+                    before: ShapeSnapshot {
+                        shape: shape.clone(),
+                        transform: *transform,
+                    },
+                    grab,
+                });
+            }
+        }
+    }
+
+    let Some(current) = drag.0.clone() else {
+        return;
+    };
+    pointer.started = false;
+    pointer.pressed = false;
+
+    let Ok((mut shape, mut transform, handle)) = shapes.get_mut(current.entity) else {
+        drag.0 = None;
+        return;
+    };
+
+    if pointer.cancel {
+        *shape = current.before.shape.clone();
+        *transform = current.before.transform;
+        if let Some(mesh) = meshes.get_mut(handle) {
+            *mesh = shape.mesh();
+        }
+        pointer.cancel = false;
+        pointer.ended = false;
+        drag.0 = None;
+        return;
+    }
+
+    if pointer.ended {
+        pointer.ended = false;
+        history.record(
+            current.entity,
+            Some(current.before.clone()),
+            Some(ShapeSnapshot {
+                shape: shape.clone(),
+                transform: *transform,
+            }),
+        );
+        event_writer.send(DrawShapeEvent::Modified(current.entity));
+        drag.0 = None;
+        return;
+    }
+
+    let Some(pointer_point) = ground_point(&ray_sources, &shape, &transform) else {
+        return;
+    };
+    let point = grid.map_or(pointer_point, |grid| grid.snap(pointer_point, &units));
+
+    let min_size = resources.min_size * units.scale();
+    let local = transform.compute_affine().inverse().transform_point3(point);
+    match (current.kind, &mut *shape) {
+        (HandleKind::Rotate, _) => {
+            let (start, _, _) = current.before.transform.rotation.to_euler(EulerRot::YXZ);
+            let mut yaw =
+                start + yaw_of(pointer_point - transform.translation) - yaw_of(current.grab);
+            if let Some(step) = gizmo_resources.rotation_snap.filter(|step| *step > 0.) {
+                yaw = (yaw / step).round() * step;
+            }
+            transform.rotation = Quat::from_rotation_y(yaw);
+        }
+        (HandleKind::Move, _) => {
+            transform.translation.x = point.x - current.grab.x;
+            transform.translation.z = point.z - current.grab.z;
+        }
+        (HandleKind::Side(side), Shape::Box(size)) => {
+            // The opposite side stays in place while the dragged side follows the pointer
+            let mut center = Vec3::ZERO;
+            if side.x != 0. {
+                let fixed = -side.x * size.x / 2.;
+                size.x = (side.x * (local.x - fixed)).max(min_size.x);
+                center.x = fixed + side.x * size.x / 2.;
+            }
+            if side.y != 0. {
+                let fixed = -side.y * size.z / 2.;
+                size.z = (side.y * (local.z - fixed)).max(min_size.y);
+                center.z = fixed + side.y * size.z / 2.;
+            }
+            let offset = transform.rotation * center;
+            transform.translation += offset;
+        }
+        (HandleKind::Radius, Shape::Cylinder { radius, .. }) => {
+            *radius = Vec2::new(local.x, local.z)
+                .length()
+                .max(min_size.min_element() / 2.);
+        }
+        (HandleKind::Vertex(i), Shape::Prism { points, .. }) => {
+            if let Some(p) = points.get_mut(i) {
+                *p = Vec2::new(local.x, local.z);
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(mesh) = meshes.get_mut(handle) {
+        *mesh = shape.mesh();
+    }
+}
+
+/// The rotation around the Y axis that turns the X axis towards the direction
+fn yaw_of(direction: Vec3) -> f32 {
+    f32::atan2(-direction.z, direction.x)
+}
+
+/// Where the pointer ray hits the horizontal plane at the bottom of the shape
+fn ground_point(
+    ray_sources: &Query<&DrawShapeRaycastSource>,
+    shape: &Shape,
+    transform: &Transform,
+) -> Option<Vec3> {
+    let ray = ray_sources.iter().find_map(|source| source.get_ray())?;
+    let hit = ray.intersects_primitive(Primitive3d::Plane {
+        point: transform.translation - Vec3::Y * shape.height() / 2.,
+        normal: Vec3::Y,
+    })?;
+    (hit.distance() >= 0.).then(|| hit.position())
+}
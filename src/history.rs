@@ -0,0 +1,235 @@
+use bevy::{
+    prelude::{
+        warn, Assets, Commands, Entity, EventReader, EventWriter, FromReflect, Handle, Mesh, Query,
+        Reflect, RemovedComponents, Res, ResMut, Resource, Transform, With,
+    },
+    utils::HashMap,
+};
+
+use crate::{
+    draw::{DrawingState, Editing},
+    style::{AppearanceQuery, ShapeAppearance},
+    BoxDrawResources, DrawShapeEvent, Shape,
+};
+
+/// Undoes the last recorded change to a drawn shape.
+/// Ignored with a warning while a shape is being drawn
+pub struct UndoEvent;
+
+/// Redoes the last undone change to a drawn shape.
+/// Ignored with a warning while a shape is being drawn
+pub struct RedoEvent;
+
+/// The geometry of a drawn shape at one point in time
+#[derive(Clone, Debug, Default, PartialEq, Reflect, FromReflect)]
+pub struct ShapeSnapshot {
+    pub shape: Shape,
+    pub transform: Transform,
+}
+
+/// A change to a single shape, `None` meaning that the shape did not exist
+#[derive(Clone, Debug)]
+struct HistoryEntry {
+    entity: Entity,
+    before: Option<ShapeSnapshot>,
+    after: Option<ShapeSnapshot>,
+    /// Kept from when the shape was last despawned, to spawn it again the way it looked
+    appearance: ShapeAppearance,
+}
+
+/// Undo and redo stacks of every shape that is spawned, redrawn or despawned by the plugin
+#[derive(Resource, Default)]
+pub struct ShapeHistory {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    /// The geometry of shapes currently being drawn, from before the drawing started
+    editing: HashMap<Entity, Option<ShapeSnapshot>>,
+}
+
+impl ShapeHistory {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Records a change made to a shape, which clears everything that could be redone
+    pub(crate) fn record(
+        &mut self,
+        entity: Entity,
+        before: Option<ShapeSnapshot>,
+        after: Option<ShapeSnapshot>,
+    ) {
+        self.record_with_appearance(entity, before, after, ShapeAppearance::default());
+    }
+
+    /// Records a change together with how the shape looks,
+    /// to spawn it the same way if it is undone or redone into existence
+    pub(crate) fn record_with_appearance(
+        &mut self,
+        entity: Entity,
+        before: Option<ShapeSnapshot>,
+        after: Option<ShapeSnapshot>,
+        appearance: ShapeAppearance,
+    ) {
+        if before == after {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(HistoryEntry {
+            entity,
+            before,
+            after,
+            appearance,
+        });
+    }
+
+    /// Shapes that are despawned and spawned again get a new entity
+    fn replace_entity(&mut self, old: Entity, new: Entity) {
+        for entry in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            if entry.entity == old {
+                entry.entity = new;
+            }
+        }
+    }
+}
+
+/// Records the geometry from before a drawing started and pairs it with the result when done
+pub(crate) fn record_history(
+    mut history: ResMut<ShapeHistory>,
+    editing: Query<(Entity, &Editing)>,
+    mut finished: RemovedComponents<Editing>,
+    shapes: Query<(&Shape, &Transform)>,
+) {
+    for (e, edit) in &editing {
+        if !history.editing.contains_key(&e) {
+            history.editing.insert(e, edit.original.clone());
+        }
+    }
+
+    for e in finished.iter() {
+        let Some(before) = history.editing.remove(&e) else {
+            continue;
+        };
+        let after = shapes.get(e).ok().map(|(shape, transform)| ShapeSnapshot {
+            shape: shape.clone(),
+            transform: *transform,
+        });
+        history.record(e, before, after);
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn undo_redo(
+    mut undo_events: EventReader<UndoEvent>,
+    mut redo_events: EventReader<RedoEvent>,
+    mut history: ResMut<ShapeHistory>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    resources: Res<BoxDrawResources>,
+    shapes: Query<(&Handle<Mesh>, AppearanceQuery), With<Shape>>,
+    editing: Query<(), With<Editing>>,
+    mut state: ResMut<DrawingState>,
+    mut event_writer: EventWriter<DrawShapeEvent>,
+) {
+    let undo = undo_events.iter().count();
+    let redo = redo_events.iter().count();
+
+    // Changing shapes while one is being drawn would leave the history inconsistent.
+    // Events are only kept for two frames, so they are dropped rather than postponed
+    if !editing.is_empty() {
+        if undo + redo > 0 {
+            warn!("Ignored {undo} undo and {redo} redo events while a shape is being drawn");
+        }
+        return;
+    }
+
+    for _ in 0..undo {
+        let Some(mut entry) = history.undo.pop() else {
+            break;
+        };
+        let snapshot = entry.before.clone();
+        let entity = restore(
+            &mut entry,
+            snapshot,
+            &mut commands,
+            &mut meshes,
+            &resources,
+            &shapes,
+            &mut state,
+            &mut event_writer,
+        );
+        history.replace_entity(entry.entity, entity);
+        history.redo.push(HistoryEntry { entity, ..entry });
+    }
+
+    for _ in 0..redo {
+        let Some(mut entry) = history.redo.pop() else {
+            break;
+        };
+        let snapshot = entry.after.clone();
+        let entity = restore(
+            &mut entry,
+            snapshot,
+            &mut commands,
+            &mut meshes,
+            &resources,
+            &shapes,
+            &mut state,
+            &mut event_writer,
+        );
+        history.replace_entity(entry.entity, entity);
+        history.undo.push(HistoryEntry { entity, ..entry });
+    }
+}
+
+/// Sets the entity of the entry to the snapshot, spawning or despawning it if needed.
+/// Returns the entity that now holds the shape
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn restore(
+    entry: &mut HistoryEntry,
+    snapshot: Option<ShapeSnapshot>,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    resources: &BoxDrawResources,
+    shapes: &Query<(&Handle<Mesh>, AppearanceQuery), With<Shape>>,
+    state: &mut DrawingState,
+    event_writer: &mut EventWriter<DrawShapeEvent>,
+) -> Entity {
+    let entity = entry.entity;
+    match (snapshot, shapes.get(entity)) {
+        (Some(snapshot), Ok((handle, ..))) => {
+            if let Some(mesh) = meshes.get_mut(handle) {
+                *mesh = snapshot.shape.mesh();
+            }
+            commands
+                .entity(entity)
+                .insert(snapshot.transform)
+                .insert(snapshot.shape);
+            event_writer.send(DrawShapeEvent::Restored(entity));
+            entity
+        }
+        (Some(snapshot), Err(_)) => {
+            let entity = entry
+                .appearance
+                .spawn(snapshot, commands, meshes, resources);
+            event_writer.send(DrawShapeEvent::Restored(entity));
+            entity
+        }
+        (None, Ok((_, appearance))) => {
+            entry.appearance = ShapeAppearance::new(appearance);
+            commands.entity(entity).despawn();
+            state.clear_redraw(entity);
+            event_writer.send(DrawShapeEvent::Despawned(entity));
+            entity
+        }
+        (None, Err(_)) => entity,
+    }
+}
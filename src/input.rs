@@ -0,0 +1,202 @@
+use bevy::prelude::{
+    debug, Entity, EventReader, KeyCode, Local, MouseButton, Query, Res, ResMut, Resource,
+    TouchInput, Vec2, Vec3, With,
+};
+use bevy_input::{touch::TouchPhase, Input};
+use bevy_mod_raycast::RaycastMethod;
+
+use crate::{DrawShapeRaycastSource, Shape};
+
+/// Which inputs are used for drawing
+#[derive(Resource)]
+pub struct DrawInputSettings {
+    /// The mouse button that draws, selects and drags the gizmo
+    pub draw_button: MouseButton,
+    /// When not empty, one of these keys has to be held for the pointer to start drawing,
+    /// which leaves the draw button free for other uses when they are not held
+    pub draw_modifiers: Vec<KeyCode>,
+    /// Any of these keys finishes a polygon or an oriented box with the points clicked so far,
+    /// finishes a box that is being dragged, or commits the height of a shape
+    pub confirm_keys: Vec<KeyCode>,
+    /// Any of these keys cancels the shape being drawn
+    pub cancel_keys: Vec<KeyCode>,
+    /// Any of these mouse buttons cancels the shape being drawn
+    pub cancel_buttons: Vec<MouseButton>,
+    /// Dragging while holding any of these keys selects shapes instead of drawing
+    pub select_keys: Vec<KeyCode>,
+    /// Holding any of these keys makes the footprint of a box square
+    pub square_keys: Vec<KeyCode>,
+    /// Holding any of these keys makes the pointer only follow the world X or Z axis,
+    /// whichever it has moved furthest along
+    pub axis_lock_keys: Vec<KeyCode>,
+    /// Holding any of these keys when drawing starts draws that box from its center,
+    /// like [`BoxDrawResources::from_center`](crate::BoxDrawResources::from_center)
+    pub center_keys: Vec<KeyCode>,
+    /// Typing numbers while a shape is drawn sets its dimensions exactly,
+    /// see [`TypedDimensions`](crate::TypedDimensions)
+    pub typed_dimensions: bool,
+    /// Any of these keys moves to typing the next dimension
+    pub next_field_keys: Vec<KeyCode>,
+    /// Any of these keys erases the last typed character
+    pub erase_keys: Vec<KeyCode>,
+    /// Draw with the mouse
+    pub mouse: bool,
+    /// Draw with touch
+    pub touch: bool,
+}
+
+impl Default for DrawInputSettings {
+    fn default() -> Self {
+        Self {
+            draw_button: MouseButton::Left,
+            draw_modifiers: Vec::new(),
+            confirm_keys: vec![KeyCode::Return, KeyCode::NumpadEnter],
+            cancel_keys: vec![KeyCode::Escape],
+            cancel_buttons: vec![MouseButton::Right],
+            select_keys: vec![KeyCode::LControl, KeyCode::RControl],
+            square_keys: vec![KeyCode::LShift, KeyCode::RShift],
+            axis_lock_keys: vec![KeyCode::LAlt, KeyCode::RAlt],
+            center_keys: vec![KeyCode::Space],
+            typed_dimensions: true,
+            next_field_keys: vec![KeyCode::Tab],
+            erase_keys: vec![KeyCode::Back],
+            mouse: true,
+            touch: true,
+        }
+    }
+}
+
+/// The pointer as seen by the drawing systems, gathered from both mouse and touch input
+#[derive(Resource, Default)]
+pub(crate) struct PointerInput {
+    /// The pointer was pressed this frame
+    pub started: bool,
+    /// The pointer was released this frame
+    pub ended: bool,
+    /// The pointer is held down
+    pub pressed: bool,
+    /// The closest intersection with a [`crate::DrawShapeRaycastMesh`] that is not a drawn shape
+    pub position: Option<Vec3>,
+    /// The drawn shape under the pointer, if it is in front of [`PointerInput::position`]
+    pub shape: Option<Entity>,
+    /// The shape being drawn should be cancelled
+    pub cancel: bool,
+    /// The shape being drawn should be finished
+    pub confirm: bool,
+    /// The pointer in screen space
+    pub cursor: Option<Vec2>,
+    /// The pointer selects shapes rather than drawing them
+    pub select: bool,
+    /// The footprint of a box should be square
+    pub square: bool,
+    /// The pointer should only follow the world X or Z axis
+    pub axis_lock: bool,
+    /// A box that starts being drawn now should be drawn from its center
+    pub from_center: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn read_pointer_input(
+    mut pointer: ResMut<PointerInput>,
+    sources: Query<&DrawShapeRaycastSource>,
+    shapes: Query<(), With<Shape>>,
+    mouse: Res<Input<MouseButton>>,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<DrawInputSettings>,
+    mut touch_events: EventReader<TouchInput>,
+    mut touch_id: Local<Option<u64>>,
+    mut touch_started: Local<bool>,
+    mut drawing_press: Local<bool>,
+) {
+    let mut started = settings.mouse && mouse.just_pressed(settings.draw_button);
+    let mut ended = settings.mouse && mouse.just_released(settings.draw_button);
+
+    for ev in touch_events.iter() {
+        if !settings.touch {
+            continue;
+        }
+        if let Some(id) = *touch_id {
+            if id != ev.id {
+                continue;
+            }
+        }
+
+        match ev.phase {
+            TouchPhase::Started => {
+                *touch_id = Some(ev.id);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if touch_id.is_some() {
+                    ended = true;
+                    *touch_started = false;
+                    *touch_id = None;
+                }
+            }
+            TouchPhase::Moved => {
+                started = !*touch_started;
+                *touch_started = true;
+            }
+        }
+    }
+
+    pointer.select = keyboard.any_pressed(settings.select_keys.iter().copied());
+    pointer.square = keyboard.any_pressed(settings.square_keys.iter().copied());
+    pointer.axis_lock = keyboard.any_pressed(settings.axis_lock_keys.iter().copied());
+    pointer.from_center = keyboard.any_pressed(settings.center_keys.iter().copied());
+    let modifier = settings.draw_modifiers.is_empty()
+        || keyboard.any_pressed(settings.draw_modifiers.iter().copied())
+        || pointer.select;
+
+    pointer.started = started && modifier;
+    pointer.ended = ended;
+    // Only a press that started drawing counts as held, so that the draw button can be used for
+    // other things, such as moving the camera, while the draw modifiers are released
+    let held = (settings.mouse && mouse.pressed(settings.draw_button)) || *touch_started;
+    *drawing_press = held && (*drawing_press || pointer.started);
+    pointer.pressed = *drawing_press;
+    (pointer.position, pointer.shape) = get_closest_intersection(&sources, &shapes);
+    pointer.cancel = keyboard.any_just_pressed(settings.cancel_keys.iter().copied())
+        || (settings.mouse && mouse.any_just_pressed(settings.cancel_buttons.iter().copied()));
+    pointer.confirm = keyboard.any_just_pressed(settings.confirm_keys.iter().copied());
+    pointer.cursor = sources.iter().find_map(|source| match source.cast_method {
+        RaycastMethod::Screenspace(cursor) => Some(cursor),
+        _ => None,
+    });
+}
+
+/// The closest intersection with anything but the drawn shapes, so that shapes are not drawn
+/// on top of themselves, together with the closest shape in front of it
+fn get_closest_intersection(
+    sources: &Query<&DrawShapeRaycastSource>,
+    shapes: &Query<(), With<Shape>>,
+) -> (Option<Vec3>, Option<Entity>) {
+    let mut intersect_position = None;
+    let mut closest_shape = None;
+    // large value, we will only pick the closest pick-source in the case of multiple pick-sources
+    let mut distance = f32::INFINITY;
+    let mut shape_distance = f32::INFINITY;
+    for source in sources {
+        for (e, intersection) in source.intersections() {
+            debug!(
+                "Distance {:?}, Position {:?}",
+                intersection.distance(),
+                intersection.position()
+            );
+            let dist = intersection.distance();
+            if shapes.contains(*e) {
+                if dist < shape_distance {
+                    shape_distance = dist;
+                    closest_shape = Some(*e);
+                }
+            } else if dist < distance {
+                distance = dist;
+                intersect_position = Some(intersection.position());
+            }
+        }
+    }
+
+    if shape_distance > distance {
+        closest_shape = None;
+    }
+    (intersect_position, closest_shape)
+}
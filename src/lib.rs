@@ -1,16 +1,61 @@
+mod command;
+mod dimension;
+#[cfg(feature = "serde")]
+mod document;
 mod draw;
 mod drawingboard;
+mod export;
+#[cfg(feature = "geojson")]
+mod geojson;
+mod gizmo;
+mod history;
+mod input;
+mod mesh;
 mod raycast;
+mod select;
+mod snap;
+mod style;
+mod units;
 
-use bevy::prelude::{CoreSet, IntoSystemConfig, IntoSystemConfigs, Plugin};
+use bevy::{
+    prelude::{CoreSet, IntoSystemConfig, IntoSystemConfigs, Plugin, Vec2},
+    utils::HashMap,
+};
 use bevy_mod_raycast::{DefaultPluginState, DefaultRaycastingPlugin, RaycastSystem};
 
+use command::shape_commands;
+pub use command::{CopyShape, DeleteShape, DuplicateShape, PasteShape, ShapeClipboard};
+use dimension::{type_dimensions, update_dimension_labels};
+pub use dimension::{Dimension, DimensionLabel, DimensionLabels, TypedDimensions};
+#[cfg(feature = "serde")]
+pub use document::{DocumentError, DocumentShape, LoadShapes, SaveShapes, ShapeDocument};
 use draw::*;
-pub use draw::{BoxDrawResources, DrawShapeEvent, DrawStateEvent, Shape};
-use drawingboard::spawn_drawingboard;
-pub use drawingboard::{DrawingboardEvent, DrawingboardResource};
-use raycast::ShapeDrawRaycastSet;
+pub use draw::{BoxDrawResources, DrawMode, DrawShapeEvent, DrawStateEvent, Shape, ShapeMetadata};
+use drawingboard::{restore_drawingboard, spawn_drawingboard};
+pub use drawingboard::{Drawingboard, DrawingboardEvent, DrawingboardResource};
+use export::export_shapes;
+pub use export::{ExportFormat, ExportShapes, ShapeExport};
+#[cfg(feature = "geojson")]
+pub use geojson::{
+    shape_to_feature, shapes_from_geojson, ExportGeoJson, GeoJsonError, GeoTransform, ImportGeoJson,
+};
+use gizmo::{drag_gizmo, gizmo_target, update_gizmo_handles, GizmoDrag, GizmoTarget};
+pub use gizmo::{ShapeGizmoEvent, ShapeGizmoResources};
+use history::{record_history, undo_redo};
+pub use history::{RedoEvent, ShapeHistory, ShapeSnapshot, UndoEvent};
+pub use input::DrawInputSettings;
+use input::{read_pointer_input, PointerInput};
 pub use raycast::{DrawShapeRaycastMesh, DrawShapeRaycastSource};
+use raycast::{ShapeDrawRaycastSet, ShapeGizmoRaycastSet};
+use select::{highlight_selection, select_shapes};
+pub use select::{
+    Selected, SelectionArea, SelectionDrag, ShapeSelectionEvent, ShapeSelectionResources,
+};
+use snap::{snap_to_grid, snap_to_shapes};
+pub use snap::{GridSnapping, ShapeSnapping, SnapIndicator, SnapTolerance};
+use style::{apply_shape_style, DrawStyle};
+pub use style::{ShapePalette, ShapeStyle};
+pub use units::{LengthUnit, ShapeUnits};
 
 struct BaseDrawShapePlugin {
     pub always_enabled: bool,
@@ -29,22 +74,122 @@ impl Plugin for BaseDrawShapePlugin {
                     .in_base_set(CoreSet::First)
                     .before(RaycastSystem::BuildRays::<ShapeDrawRaycastSet>),
             );
+        app.add_plugin(DefaultRaycastingPlugin::<ShapeGizmoRaycastSet>::default())
+            .add_system(
+                raycast::update_gizmo_raycast
+                    .in_base_set(CoreSet::First)
+                    .after(raycast::update_raycast_with_cursor)
+                    .after(raycast::update_raycast_with_touch)
+                    .before(RaycastSystem::BuildRays::<ShapeGizmoRaycastSet>),
+            );
+
+        // Reflection, for drawn shapes to be saved in scenes
+        app.register_type::<Shape>()
+            .register_type::<ShapeMetadata>()
+            .register_type::<ShapeSnapshot>()
+            .register_type::<ShapeStyle>()
+            .register_type::<Editing>()
+            .register_type::<Drawingboard>()
+            .register_type::<Vec<Vec2>>()
+            .register_type::<Option<ShapeSnapshot>>()
+            .register_type::<HashMap<String, String>>();
 
         // Drawing
         app.init_resource::<BoxDrawResources>()
             .init_resource::<DrawingState>()
-            .init_resource::<TouchId>()
+            .init_resource::<DrawMode>()
+            .init_resource::<PointerInput>()
+            .init_resource::<DrawInputSettings>()
+            .init_resource::<ShapeUnits>()
+            .init_resource::<DrawEventQueue>()
             .add_event::<DrawShapeEvent>()
             .add_event::<DrawStateEvent>()
-            .add_system(draw_box.in_base_set(CoreSet::First))
+            .add_systems(
+                (
+                    read_pointer_input,
+                    snap_to_grid,
+                    snap_to_shapes,
+                    drag_gizmo,
+                    select_shapes,
+                    send_draw_events,
+                    cancel_drawing,
+                    draw_box,
+                )
+                    .chain()
+                    .in_base_set(CoreSet::First)
+                    .after(RaycastSystem::UpdateIntersections::<ShapeDrawRaycastSet>)
+                    .after(RaycastSystem::UpdateRaycast::<ShapeGizmoRaycastSet>),
+            )
             .add_system(edit_box)
-            .add_system(draw_state);
+            .add_system(edit_height)
+            .add_system(type_dimensions.after(edit_box).after(edit_height))
+            .add_system(update_dimension_labels.after(type_dimensions))
+            .add_system(draw_state)
+            .add_system(restore_shape_meshes.in_base_set(CoreSet::PreUpdate))
+            .add_system(update_shape_bounds);
+
+        // Gizmo
+        app.init_resource::<ShapeGizmoResources>()
+            .init_resource::<GizmoTarget>()
+            .init_resource::<GizmoDrag>()
+            .add_event::<ShapeGizmoEvent>()
+            .add_systems((gizmo_target, update_gizmo_handles).chain());
+
+        // Style
+        app.init_resource::<ShapePalette>()
+            .init_resource::<DrawStyle>()
+            .add_system(apply_shape_style.before(highlight_selection));
+
+        // Selection
+        app.init_resource::<ShapeSelectionResources>()
+            .init_resource::<SelectionDrag>()
+            .add_event::<ShapeSelectionEvent>()
+            .add_system(highlight_selection);
+
+        // History and commands
+        app.init_resource::<ShapeHistory>()
+            .init_resource::<ShapeClipboard>()
+            .add_event::<DeleteShape>()
+            .add_event::<DuplicateShape>()
+            .add_event::<CopyShape>()
+            .add_event::<PasteShape>()
+            .add_event::<UndoEvent>()
+            .add_event::<RedoEvent>()
+            .add_systems(
+                (record_history, undo_redo, shape_commands)
+                    .chain()
+                    .in_base_set(CoreSet::PreUpdate),
+            );
+
+        // Documents
+        #[cfg(feature = "serde")]
+        app.add_event::<SaveShapes>()
+            .add_event::<LoadShapes>()
+            .add_system(
+                document::shape_documents
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(shape_commands),
+            );
+
+        // Export
+        app.add_event::<ExportShapes>().add_system(export_shapes);
+
+        // GeoJSON
+        #[cfg(feature = "geojson")]
+        app.add_event::<ExportGeoJson>()
+            .add_event::<ImportGeoJson>()
+            .add_system(
+                geojson::geojson_files
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(shape_commands),
+            );
 
         // Drawingboard
         if self.enable_drawingboard {
             app.init_resource::<DrawingboardResource>()
                 .add_event::<DrawingboardEvent>()
-                .add_system(spawn_drawingboard);
+                .add_system(spawn_drawingboard)
+                .add_system(restore_drawingboard.in_base_set(CoreSet::PreUpdate));
         }
 
         if self.always_enabled {
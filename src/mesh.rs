@@ -0,0 +1,306 @@
+use bevy::{
+    prelude::{Mesh, Vec2, Vec3},
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+
+/// Creates a mesh of the footprint `points` extruded along the Y axis, centered around the
+/// origin in height. The footprint may be concave but should not intersect itself.
+/// Caps are only added when there are at least three points, so a footprint of two points is a
+/// single wall, which is used as a preview while the footprint is clicked.
+pub(crate) fn prism(points: &[Vec2], height: f32) -> Mesh {
+    let mut points = points.to_vec();
+    if signed_area(&points) < 0. {
+        points.reverse();
+    }
+
+    let half = height / 2.;
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    if points.len() >= 3 {
+        let triangles = triangulate(&points);
+        for (y, normal) in [(half, 1.), (-half, -1.)] {
+            let start = positions.len() as u32;
+            for p in &points {
+                positions.push([p.x, y, p.y]);
+                normals.push([0., normal, 0.]);
+                uvs.push([p.x, p.y]);
+            }
+            for t in triangles.chunks_exact(3) {
+                // Counter-clockwise in the XZ plane faces downwards
+                if normal > 0. {
+                    indices.extend([start + t[0], start + t[2], start + t[1]]);
+                } else {
+                    indices.extend([start + t[0], start + t[1], start + t[2]]);
+                }
+            }
+        }
+    }
+
+    let edges = if points.len() > 2 {
+        points.len()
+    } else {
+        points.len().saturating_sub(1)
+    };
+    let mut distance = 0.;
+    for i in 0..edges {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        let d = b - a;
+        let normal = Vec3::new(d.y, 0., -d.x).normalize_or_zero();
+        let length = d.length();
+
+        let start = positions.len() as u32;
+        positions.extend([
+            [a.x, -half, a.y],
+            [b.x, -half, b.y],
+            [b.x, half, b.y],
+            [a.x, half, a.y],
+        ]);
+        normals.extend([normal.to_array(); 4]);
+        uvs.extend([
+            [distance, 0.],
+            [distance + length, 0.],
+            [distance + length, height],
+            [distance, height],
+        ]);
+        indices.extend([start, start + 2, start + 1, start, start + 3, start + 2]);
+        distance += length;
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Twice the signed area of the polygon, positive when the points are counter-clockwise
+pub(crate) fn signed_area(points: &[Vec2]) -> f32 {
+    let mut area = 0.;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.perp_dot(b);
+    }
+    area
+}
+
+/// Ear clipping triangulation of a counter-clockwise polygon.
+/// Falls back to a fan for whatever is left if the polygon intersects itself.
+fn triangulate(points: &[Vec2]) -> Vec<u32> {
+    let mut remaining: Vec<u32> = (0..points.len() as u32).collect();
+    let mut indices = Vec::with_capacity((points.len() - 2) * 3);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let prev = points[remaining[(i + n - 1) % n] as usize];
+            let curr = points[remaining[i] as usize];
+            let next = points[remaining[(i + 1) % n] as usize];
+
+            if (curr - prev).perp_dot(next - curr) <= 0. {
+                return false;
+            }
+
+            remaining.iter().all(|&j| {
+                let p = points[j as usize];
+                p == prev || p == curr || p == next || !in_triangle(p, prev, curr, next)
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                indices.extend([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    for i in 1..remaining.len() - 1 {
+        indices.extend([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    indices
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(p - a) >= 0. && (c - b).perp_dot(p - b) >= 0. && (a - c).perp_dot(p - c) >= 0.
+}
+
+/// Creates a flat mesh of the text in the XY plane facing the Z axis, centered around the origin.
+/// Glyphs are drawn with straight strokes and only digits, `.`, `-`, `_`, `'`, `"`, spaces and the
+/// letters of common length units are supported; other characters are left blank
+pub(crate) fn text(text: &str, size: f32) -> Mesh {
+    // Glyphs are drawn on a grid two units wide and four units high
+    let unit = size / 4.;
+    let thickness = unit * 0.4;
+
+    let mut strokes = Vec::new();
+    let mut x = 0.;
+    for c in text.chars() {
+        let (width, glyph) = glyph(c);
+        strokes.extend(
+            glyph
+                .iter()
+                .map(|[x0, y0, x1, y1]| (Vec2::new(x + x0, *y0), Vec2::new(x + x1, *y1))),
+        );
+        x += width + 1.;
+    }
+    let center = Vec2::new((x - 1.).max(0.) / 2., 2.);
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for (a, b) in strokes {
+        let (a, b) = ((a - center) * unit, (b - center) * unit);
+        let along = (b - a).try_normalize().unwrap_or(Vec2::Y) * thickness / 2.;
+        let across = along.perp();
+        let start = positions.len() as u32;
+        positions.extend(
+            [
+                a - along - across,
+                b + along - across,
+                b + along + across,
+                a - along + across,
+            ]
+            .map(|p| [p.x, p.y, 0.]),
+        );
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    let count = positions.len();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 0., 1.]; count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; count]);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// The width and the strokes of a glyph
+fn glyph(c: char) -> (f32, &'static [[f32; 4]]) {
+    // The segments of a seven-segment display
+    const A: [f32; 4] = [0., 4., 2., 4.];
+    const B: [f32; 4] = [2., 4., 2., 2.];
+    const C: [f32; 4] = [2., 2., 2., 0.];
+    const D: [f32; 4] = [0., 0., 2., 0.];
+    const E: [f32; 4] = [0., 0., 0., 2.];
+    const F: [f32; 4] = [0., 2., 0., 4.];
+    const G: [f32; 4] = [0., 2., 2., 2.];
+
+    match c {
+        '0' => (2., &[A, B, C, D, E, F]),
+        '1' => (2., &[B, C]),
+        '2' => (2., &[A, B, G, E, D]),
+        '3' => (2., &[A, B, G, C, D]),
+        '4' => (2., &[F, G, B, C]),
+        '5' => (2., &[A, F, G, C, D]),
+        '6' => (2., &[A, F, G, E, D, C]),
+        '7' => (2., &[A, B, C]),
+        '8' => (2., &[A, B, C, D, E, F, G]),
+        '9' => (2., &[A, B, F, G, C, D]),
+        '-' => (2., &[G]),
+        '_' => (2., &[D]),
+        '.' => (0., &[[0., 0., 0., 0.4]]),
+        '\'' => (0., &[[0., 4., 0., 3.]]),
+        '"' => (1., &[[0., 4., 0., 3.], [1., 4., 1., 3.]]),
+        'c' => (2., &[[2., 2., 0., 2.], E, D]),
+        'f' => (2., &[[2., 4., 1., 4.], [1., 4., 1., 0.], G]),
+        'i' => (0., &[[0., 0., 0., 2.], [0., 3., 0., 3.5]]),
+        'm' => (2., &[E, G, [1., 2., 1., 0.], C]),
+        'n' => (2., &[E, G, C]),
+        't' => (2., &[[1., 4., 1., 0.], [1., 0., 2., 0.], G]),
+        _ => (1., &[]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::VertexAttributeValues;
+
+    use super::*;
+
+    /// An L shape, which has a concave corner at (1, 1)
+    fn l_shape() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0., 0.),
+            Vec2::new(2., 0.),
+            Vec2::new(2., 1.),
+            Vec2::new(1., 1.),
+            Vec2::new(1., 2.),
+            Vec2::new(0., 2.),
+        ]
+    }
+
+    fn contains(points: &[Vec2], p: Vec2) -> bool {
+        let mut inside = false;
+        for (i, a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// The corners of every triangle of the mesh
+    fn triangles(mesh: &Mesh) -> Vec<[Vec3; 3]> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("the mesh has no positions");
+        };
+        let indices = mesh.indices().unwrap().iter().collect::<Vec<_>>();
+        indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]].map(|i| Vec3::from(positions[i])))
+            .collect()
+    }
+
+    fn assert_closed_and_outward(points: &[Vec2], height: f32) {
+        let mesh = prism(points, height);
+        let triangles = triangles(&mesh);
+        // Two caps of n - 2 triangles and a wall of two triangles for every edge
+        let n = points.len();
+        assert_eq!(triangles.len(), 2 * (n - 2) + 2 * n);
+
+        let mut cap_area = 0.;
+        for [a, b, c] in triangles {
+            let normal = (b - a).cross(c - a);
+            let center = (a + b + c) / 3.;
+            if (a.y - b.y).abs() < 1e-6 && (a.y - c.y).abs() < 1e-6 {
+                assert!(normal.y * center.y > 0., "a cap faces inwards at {center}");
+                if center.y > 0. {
+                    cap_area += normal.length() / 2.;
+                }
+            } else {
+                let outside = center + normal.normalize() * 0.01;
+                assert!(
+                    !contains(points, Vec2::new(outside.x, outside.z)),
+                    "a wall faces inwards at {center}"
+                );
+            }
+        }
+        // The triangles of the top cover the footprint exactly once
+        assert!((cap_area - signed_area(points).abs() / 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn concave_prism() {
+        assert_closed_and_outward(&l_shape(), 1.5);
+    }
+
+    #[test]
+    fn clockwise_prism() {
+        let mut points = l_shape();
+        points.reverse();
+        assert!(signed_area(&points) < 0.);
+        assert_closed_and_outward(&points, 0.5);
+    }
+}
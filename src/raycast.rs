@@ -1,21 +1,53 @@
 use bevy::{
-    prelude::{Camera, EventReader, Local, Query, TouchInput},
+    prelude::{
+        Camera, Commands, Entity, EventReader, Local, Query, Res, TouchInput, With, Without,
+    },
     reflect::Reflect,
     window::CursorMoved,
 };
 use bevy_input::touch::TouchPhase;
 use bevy_mod_raycast::{RaycastMesh, RaycastMethod, RaycastSource};
 
+use crate::DrawInputSettings;
+
 #[derive(Debug, Clone, Reflect)]
 pub struct ShapeDrawRaycastSet;
 
 pub type DrawShapeRaycastMesh = RaycastMesh<ShapeDrawRaycastSet>;
 pub type DrawShapeRaycastSource = RaycastSource<ShapeDrawRaycastSet>;
 
+/// Raycasting against the gizmo handles, kept apart from the surfaces that are drawn on
+#[derive(Debug, Clone, Reflect)]
+pub struct ShapeGizmoRaycastSet;
+
+pub(crate) type GizmoRaycastMesh = RaycastMesh<ShapeGizmoRaycastSet>;
+pub(crate) type GizmoRaycastSource = RaycastSource<ShapeGizmoRaycastSet>;
+
+/// Gives every [`DrawShapeRaycastSource`] a matching gizmo source that casts the same ray
+pub(crate) fn update_gizmo_raycast(
+    mut commands: Commands,
+    new_sources: Query<Entity, (With<DrawShapeRaycastSource>, Without<GizmoRaycastSource>)>,
+    mut sources: Query<(&DrawShapeRaycastSource, &mut GizmoRaycastSource)>,
+) {
+    for e in &new_sources {
+        commands.entity(e).insert(GizmoRaycastSource::new());
+    }
+
+    for (source, mut gizmo_source) in &mut sources {
+        gizmo_source.cast_method = source.cast_method.clone();
+    }
+}
+
 pub(crate) fn update_raycast_with_cursor(
     mut cursor: EventReader<CursorMoved>,
     mut query: Query<&mut DrawShapeRaycastSource>,
+    settings: Res<DrawInputSettings>,
 ) {
+    if !settings.mouse {
+        cursor.clear();
+        return;
+    }
+
     let cursor_position = match cursor.iter().last() {
         Some(cursor_moved) => cursor_moved.position,
         None => return,
@@ -30,7 +62,13 @@ pub(crate) fn update_raycast_with_touch(
     mut touch: EventReader<TouchInput>,
     mut query: Query<(&mut DrawShapeRaycastSource, &Camera)>,
     mut current_touch: Local<Option<u64>>,
+    settings: Res<DrawInputSettings>,
 ) {
+    if !settings.touch {
+        touch.clear();
+        return;
+    }
+
     'events: for ev in touch.iter() {
         if let Some(id) = *current_touch {
             if id != ev.id {
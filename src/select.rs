@@ -0,0 +1,225 @@
+use bevy::prelude::{
+    Added, Assets, Camera, Color, Commands, Component, Entity, EventWriter, FromWorld,
+    GlobalTransform, Handle, Query, RemovedComponents, Res, ResMut, Resource, StandardMaterial,
+    Transform, Vec2, With, Without, World,
+};
+
+use crate::{
+    draw::{DrawingState, Editing},
+    input::PointerInput,
+    DrawShapeRaycastSource, Shape,
+};
+
+/// Marks a drawn shape as selected, which shows it with
+/// [`ShapeSelectionResources::highlight_material`].
+/// Managed by clicking on shapes, but may also be inserted and removed directly
+#[derive(Component, Default)]
+pub struct Selected;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ShapeSelectionEvent {
+    Selected(Entity),
+    Deselected(Entity),
+}
+
+/// How shapes are selected by dragging over empty space
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionArea {
+    /// Selects everything inside the rectangle between the press and the pointer
+    #[default]
+    Rectangle,
+    /// Selects everything inside the path the pointer is dragged along
+    Lasso,
+}
+
+#[derive(Resource)]
+pub struct ShapeSelectionResources {
+    /// Replaces the material of a shape while it is selected
+    pub highlight_material: Handle<StandardMaterial>,
+    pub area: SelectionArea,
+}
+
+impl FromWorld for ShapeSelectionResources {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world
+            .get_resource_mut::<Assets<StandardMaterial>>()
+            .unwrap();
+
+        let highlight_material = materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.55, 0.1),
+            ..Default::default()
+        });
+
+        Self {
+            highlight_material,
+            area: SelectionArea::default(),
+        }
+    }
+}
+
+/// The material a selected shape had before it was highlighted
+#[derive(Component)]
+pub(crate) struct Unhighlighted(pub Handle<StandardMaterial>);
+
+/// The material of a shape without the highlight of the selection
+pub(crate) fn base_material<'a>(
+    material: &'a Handle<StandardMaterial>,
+    unhighlighted: Option<&'a Unhighlighted>,
+) -> &'a Handle<StandardMaterial> {
+    unhighlighted.map_or(material, |unhighlighted| &unhighlighted.0)
+}
+
+/// The selection that is being dragged over the screen
+#[derive(Resource, Default)]
+pub struct SelectionDrag {
+    points: Vec<Vec2>,
+}
+
+impl SelectionDrag {
+    /// The points in screen space of the selection being dragged, which are the two opposite
+    /// corners of a [`SelectionArea::Rectangle`] or the path of a [`SelectionArea::Lasso`].
+    /// Empty while nothing is being dragged
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    fn contains(&self, area: SelectionArea, point: Vec2) -> bool {
+        match (area, self.points.as_slice()) {
+            (SelectionArea::Rectangle, [a, b, ..]) => {
+                let (min, max) = (a.min(*b), a.max(*b));
+                point.cmpge(min).all() && point.cmple(max).all()
+            }
+            (SelectionArea::Lasso, points) if points.len() >= 3 => {
+                // Even-odd rule, the lasso is closed between the last and the first point
+                let mut inside = false;
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    if (a.y > point.y) != (b.y > point.y)
+                        && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                    {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Selects the shape that is clicked and deselects everything else.
+/// Pressing on empty space while not drawing, or while holding one of
+/// [`crate::DrawInputSettings::select_keys`], drags a [`SelectionArea`] instead, selecting
+/// every shape with its footprint inside the area as seen from any of the cameras.
+/// A click that selects is captured so that it does not start a new drawing as well
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn select_shapes(
+    mut commands: Commands,
+    mut pointer: ResMut<PointerInput>,
+    mut drag: ResMut<SelectionDrag>,
+    resources: Res<ShapeSelectionResources>,
+    state: Res<DrawingState>,
+    editing: Query<(), With<Editing>>,
+    selected: Query<Entity, With<Selected>>,
+    shapes: Query<(Entity, &Shape, &Transform)>,
+    cameras: Query<(&Camera, &GlobalTransform), With<DrawShapeRaycastSource>>,
+) {
+    if !drag.points.is_empty() {
+        pointer.started = false;
+        pointer.pressed = false;
+
+        if pointer.cancel {
+            pointer.cancel = false;
+            drag.points.clear();
+            return;
+        }
+
+        if let Some(cursor) = pointer.cursor {
+            match resources.area {
+                SelectionArea::Rectangle => {
+                    drag.points.truncate(1);
+                    drag.points.push(cursor);
+                }
+                SelectionArea::Lasso => {
+                    if drag
+                        .points
+                        .last()
+                        .map_or(true, |last| last.distance(cursor) >= 1.)
+                    {
+                        drag.points.push(cursor);
+                    }
+                }
+            }
+        }
+
+        if pointer.ended {
+            pointer.ended = false;
+            for (e, shape, transform) in &shapes {
+                let outline = shape.world_outline(transform);
+                let inside = cameras.iter().any(|(camera, camera_transform)| {
+                    outline.iter().all(|p| {
+                        camera
+                            .world_to_viewport(camera_transform, *p)
+                            .map_or(false, |p| drag.contains(resources.area, p))
+                    })
+                });
+                if inside && !selected.contains(e) {
+                    commands.entity(e).insert(Selected);
+                }
+            }
+            drag.points.clear();
+        }
+        return;
+    }
+
+    // Clicks belong to the shape being drawn or redrawn
+    if !pointer.started || !editing.is_empty() || matches!(*state, DrawingState::Idle(Some(_))) {
+        return;
+    }
+
+    for e in &selected {
+        if Some(e) != pointer.shape {
+            commands.entity(e).remove::<Selected>();
+        }
+    }
+
+    if let Some(e) = pointer.shape {
+        if !selected.contains(e) {
+            commands.entity(e).insert(Selected);
+        }
+    } else if pointer.select || matches!(*state, DrawingState::Disabled) {
+        let Some(cursor) = pointer.cursor else {
+            return;
+        };
+        drag.points.push(cursor);
+    } else {
+        return;
+    }
+    pointer.started = false;
+    pointer.pressed = false;
+}
+
+/// Swaps the material of shapes as they are selected and deselected
+#[allow(clippy::type_complexity)]
+pub(crate) fn highlight_selection(
+    mut commands: Commands,
+    resources: Res<ShapeSelectionResources>,
+    mut deselected: RemovedComponents<Selected>,
+    mut unhighlighted: Query<(&Unhighlighted, &mut Handle<StandardMaterial>), Without<Selected>>,
+    mut selected: Query<(Entity, &mut Handle<StandardMaterial>), (With<Selected>, Added<Selected>)>,
+    mut event_writer: EventWriter<ShapeSelectionEvent>,
+) {
+    for e in deselected.iter() {
+        if let Ok((original, mut material)) = unhighlighted.get_mut(e) {
+            *material = original.0.clone();
+            commands.entity(e).remove::<Unhighlighted>();
+        }
+        event_writer.send(ShapeSelectionEvent::Deselected(e));
+    }
+
+    for (e, mut material) in &mut selected {
+        commands.entity(e).insert(Unhighlighted(material.clone()));
+        *material = resources.highlight_material.clone();
+        event_writer.send(ShapeSelectionEvent::Selected(e));
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::prelude::{
+    shape, Assets, BVec3, Camera, Color, Commands, Component, GlobalTransform, Mesh, PbrBundle,
+    Query, Res, ResMut, Resource, StandardMaterial, Transform, Vec2, Vec3, Visibility, With,
+    Without,
+};
+
+use crate::{
+    draw::{DrawingState, Editing},
+    input::PointerInput,
+    DrawShapeRaycastSource, Shape, ShapeUnits,
+};
+
+/// Snaps the corners of drawn shapes to a grid while this resource exists
+#[derive(Resource, Clone, Debug)]
+pub struct GridSnapping {
+    /// The distance between grid lines in the display unit of [`ShapeUnits`]
+    pub size: f32,
+    /// A point in world space that lies on the grid
+    pub origin: Vec3,
+    /// Which axes are snapped, by default only the horizontal ones
+    pub axes: BVec3,
+}
+
+impl Default for GridSnapping {
+    fn default() -> Self {
+        Self {
+            size: 0.5,
+            origin: Vec3::ZERO,
+            axes: BVec3::new(true, false, true),
+        }
+    }
+}
+
+impl GridSnapping {
+    pub fn snap(&self, position: Vec3, units: &ShapeUnits) -> Vec3 {
+        let size = units.to_world(self.size);
+        if size <= 0. {
+            return position;
+        }
+        let snapped = ((position - self.origin) / size).round() * size + self.origin;
+        Vec3::select(self.axes, snapped, position)
+    }
+}
+
+pub(crate) fn snap_to_grid(
+    mut pointer: ResMut<PointerInput>,
+    grid: Option<Res<GridSnapping>>,
+    units: Res<ShapeUnits>,
+) {
+    let Some(grid) = grid else {
+        return;
+    };
+    pointer.position = pointer.position.map(|position| grid.snap(position, &units));
+}
+
+/// How close the pointer must be to a corner or edge to snap to it
+#[derive(Clone, Copy, Debug)]
+pub enum SnapTolerance {
+    /// Distance in the XZ plane in the display unit of [`ShapeUnits`]
+    World(f32),
+    /// Distance in logical pixels on the screen
+    Screen(f32),
+}
+
+/// Snaps the pointer to the corners and edges of other drawn shapes while this resource exists.
+/// Corners take precedence over edges
+#[derive(Resource, Clone, Debug)]
+pub struct ShapeSnapping {
+    pub tolerance: SnapTolerance,
+    pub vertices: bool,
+    pub edges: bool,
+    /// Shows a marker at the point that is snapped to, sized in meters like the gizmo handles
+    pub indicator: bool,
+}
+
+impl Default for ShapeSnapping {
+    fn default() -> Self {
+        Self {
+            tolerance: SnapTolerance::Screen(10.),
+            vertices: true,
+            edges: true,
+            indicator: true,
+        }
+    }
+}
+
+/// Marks the entity showing the active snap target
+#[derive(Component)]
+pub struct SnapIndicator;
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn snap_to_shapes(
+    mut pointer: ResMut<PointerInput>,
+    settings: Option<Res<ShapeSnapping>>,
+    state: Res<DrawingState>,
+    units: Res<ShapeUnits>,
+    shapes: Query<(&Shape, &Transform), Without<Editing>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<DrawShapeRaycastSource>>,
+    mut indicator: Query<(&mut Transform, &mut Visibility), (With<SnapIndicator>, Without<Shape>)>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let target = match (&settings, pointer.position, &*state) {
+        (Some(settings), Some(position), DrawingState::Idle(_)) => {
+            find_snap_target(settings, position, &units, &shapes, &cameras)
+        }
+        _ => None,
+    };
+
+    if let Some(target) = target {
+        pointer.position = Some(target);
+    }
+
+    let show = settings.as_ref().map_or(false, |s| s.indicator);
+    match indicator.get_single_mut() {
+        Ok((mut transform, mut visibility)) => match target {
+            Some(target) if show => {
+                transform.translation = target;
+                // The marker is sized in meters
+                transform.scale = Vec3::splat(units.from_meters(1.));
+                *visibility = Visibility::Visible;
+            }
+            _ => *visibility = Visibility::Hidden,
+        },
+        Err(_) if show => {
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::UVSphere {
+                        radius: 0.03,
+                        ..Default::default()
+                    })),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::YELLOW,
+                        unlit: true,
+                        ..Default::default()
+                    }),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                SnapIndicator,
+            ));
+        }
+        Err(_) => {}
+    }
+}
+
+fn find_snap_target(
+    settings: &ShapeSnapping,
+    position: Vec3,
+    units: &ShapeUnits,
+    shapes: &Query<(&Shape, &Transform), Without<Editing>>,
+    cameras: &Query<(&Camera, &GlobalTransform), With<DrawShapeRaycastSource>>,
+) -> Option<Vec3> {
+    let (tolerance, camera) = match settings.tolerance {
+        SnapTolerance::World(tolerance) => (units.to_world(tolerance), None),
+        SnapTolerance::Screen(tolerance) => (tolerance, Some(cameras.iter().next()?)),
+    };
+
+    // Only the horizontal position is snapped, the pointer stays on the surface it is on
+    let candidate = |p: Vec2| Vec3::new(p.x, position.y, p.y);
+    let distance = |p: Vec3| match camera {
+        Some((camera, camera_transform)) => {
+            let a = camera.world_to_viewport(camera_transform, p)?;
+            let b = camera.world_to_viewport(camera_transform, position)?;
+            Some(a.distance(b))
+        }
+        None => Some(Vec2::new(p.x - position.x, p.z - position.z).length()),
+    };
+    let closest = |points: Vec<Vec3>| {
+        points
+            .into_iter()
+            .filter_map(|p| Some((p, distance(p)?)))
+            .filter(|(_, d)| *d <= tolerance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(p, _)| p)
+    };
+
+    let pointer = Vec2::new(position.x, position.z);
+    let outlines: Vec<(bool, Vec<Vec2>)> = shapes
+        .iter()
+        .map(|(shape, transform)| {
+            let outline = shape
+                .world_outline(transform)
+                .into_iter()
+                .map(|p| Vec2::new(p.x, p.z))
+                .collect();
+            (matches!(shape, Shape::Cylinder { .. }), outline)
+        })
+        .collect();
+
+    if settings.vertices {
+        let vertices = outlines
+            .iter()
+            .filter(|(round, _)| !round)
+            .flat_map(|(_, outline)| outline.iter().map(|p| candidate(*p)));
+        if let Some(target) = closest(vertices.collect()) {
+            return Some(target);
+        }
+    }
+
+    if settings.edges {
+        let edges = outlines.iter().flat_map(|(_, outline)| {
+            (0..outline.len()).map(move |i| {
+                let a = outline[i];
+                let b = outline[(i + 1) % outline.len()];
+                candidate(closest_on_segment(pointer, a, b))
+            })
+        });
+        if let Some(target) = closest(edges.collect()) {
+            return Some(target);
+        }
+    }
+
+    None
+}
+
+fn closest_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length = ab.length_squared();
+    if length <= f32::EPSILON {
+        return a;
+    }
+    a + ab * ((p - a).dot(ab) / length).clamp(0., 1.)
+}
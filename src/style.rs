@@ -0,0 +1,165 @@
+use bevy::{
+    prelude::{
+        warn, AlphaMode, Assets, Color, Commands, Component, DetectChanges, Entity, FromReflect,
+        Handle, Mesh, Query, Ref, Reflect, ReflectComponent, RemovedComponents, Res, ResMut,
+        Resource, StandardMaterial, With, Without,
+    },
+    utils::HashMap,
+};
+
+use crate::{
+    draw::shape_bundle,
+    select::{base_material, Unhighlighted},
+    BoxDrawResources, Shape, ShapeMetadata, ShapeSnapshot,
+};
+
+/// Named materials for drawn shapes, used by [`ShapeStyle::Palette`]
+#[derive(Resource, Default)]
+pub struct ShapePalette {
+    materials: HashMap<String, Handle<StandardMaterial>>,
+}
+
+impl ShapePalette {
+    pub fn insert(&mut self, name: impl Into<String>, material: Handle<StandardMaterial>) {
+        self.materials.insert(name.into(), material);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Handle<StandardMaterial>> {
+        self.materials.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Handle<StandardMaterial>> {
+        self.materials.remove(name)
+    }
+}
+
+/// How a drawn shape looks. The plugin sets the material of the shape whenever this changes
+#[derive(Component, Clone, Debug, Default, PartialEq, Reflect, FromReflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShapeStyle {
+    /// [`BoxDrawResources::material`]
+    #[default]
+    Default,
+    /// The material with this name in the [`ShapePalette`]
+    Palette(String),
+    /// A material of this color, blended if it is transparent
+    Color(Color),
+}
+
+/// The style of shapes that are drawn from now on
+#[derive(Resource, Default)]
+pub(crate) struct DrawStyle(pub ShapeStyle);
+
+/// What a shape looks like besides its geometry, kept to spawn it again the same way
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ShapeAppearance {
+    pub material: Option<Handle<StandardMaterial>>,
+    pub metadata: Option<ShapeMetadata>,
+    pub style: Option<ShapeStyle>,
+}
+
+/// The components a [`ShapeAppearance`] is read from
+pub(crate) type AppearanceQuery<'a> = (
+    &'a Handle<StandardMaterial>,
+    Option<&'a Unhighlighted>,
+    Option<&'a ShapeMetadata>,
+    Option<&'a ShapeStyle>,
+);
+
+impl ShapeAppearance {
+    pub fn new((material, unhighlighted, metadata, style): AppearanceQuery) -> Self {
+        Self {
+            material: Some(base_material(material, unhighlighted).clone()),
+            metadata: metadata.cloned(),
+            style: style.cloned(),
+        }
+    }
+
+    /// Spawns a drawn shape that looks like this
+    pub fn spawn(
+        &self,
+        snapshot: ShapeSnapshot,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        resources: &BoxDrawResources,
+    ) -> Entity {
+        let mesh = meshes.add(snapshot.shape.mesh());
+        let material = self
+            .material
+            .clone()
+            .unwrap_or_else(|| resources.material.clone());
+        let mut e_commands = commands.spawn(shape_bundle(
+            snapshot.shape,
+            snapshot.transform,
+            mesh,
+            material,
+        ));
+        if let Some(metadata) = &self.metadata {
+            e_commands.insert(metadata.clone());
+        }
+        if let Some(style) = &self.style {
+            e_commands.insert(style.clone());
+        }
+        e_commands.id()
+    }
+}
+
+/// Sets the material of shapes when their [`ShapeStyle`] or the [`ShapePalette`] changes.
+/// Selected shapes keep their highlight and get the new material when deselected
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_shape_style(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    resources: Res<BoxDrawResources>,
+    palette: Res<ShapePalette>,
+    mut styled: Query<(
+        Ref<ShapeStyle>,
+        &mut Handle<StandardMaterial>,
+        Option<&mut Unhighlighted>,
+    )>,
+    mut unstyled: Query<
+        (&mut Handle<StandardMaterial>, Option<&mut Unhighlighted>),
+        (With<Shape>, Without<ShapeStyle>),
+    >,
+    mut removed: RemovedComponents<ShapeStyle>,
+) {
+    for (style, mut material, unhighlighted) in &mut styled {
+        let from_palette = matches!(*style, ShapeStyle::Palette(_)) && palette.is_changed();
+        if !style.is_changed() && !from_palette {
+            continue;
+        }
+
+        let new_material = match &*style {
+            ShapeStyle::Default => resources.material.clone(),
+            ShapeStyle::Palette(name) => match palette.get(name) {
+                Some(material) => material.clone(),
+                None => {
+                    warn!("There is no material named {name} in the shape palette");
+                    resources.material.clone()
+                }
+            },
+            ShapeStyle::Color(color) => materials.add(StandardMaterial {
+                base_color: *color,
+                alpha_mode: if color.a() < 1. {
+                    AlphaMode::Blend
+                } else {
+                    AlphaMode::Opaque
+                },
+                ..Default::default()
+            }),
+        };
+        match unhighlighted {
+            Some(mut unhighlighted) => unhighlighted.0 = new_material,
+            None => *material = new_material,
+        }
+    }
+
+    for e in removed.iter() {
+        if let Ok((mut material, unhighlighted)) = unstyled.get_mut(e) {
+            match unhighlighted {
+                Some(mut unhighlighted) => unhighlighted.0 = resources.material.clone(),
+                None => *material = resources.material.clone(),
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::Resource;
+
+/// A unit that lengths are shown and configured in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LengthUnit {
+    #[default]
+    Meter,
+    Centimeter,
+    Foot,
+    Inch,
+}
+
+impl LengthUnit {
+    /// The length of the unit in meters
+    pub fn meters(&self) -> f32 {
+        match self {
+            LengthUnit::Meter => 1.,
+            LengthUnit::Centimeter => 0.01,
+            LengthUnit::Foot => 0.3048,
+            LengthUnit::Inch => 0.0254,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            LengthUnit::Meter => "m",
+            LengthUnit::Centimeter => "cm",
+            LengthUnit::Foot => "ft",
+            LengthUnit::Inch => "in",
+        }
+    }
+}
+
+/// How world units relate to real lengths, and which unit lengths are shown in.
+/// Sizes in the settings of the plugin, such as [`BoxDrawResources::initial_height`](crate::BoxDrawResources::initial_height)
+/// and [`GridSnapping::size`](crate::GridSnapping::size), are given in the display unit,
+/// and lengths reported by the plugin are converted to it
+#[derive(Resource, Clone, Debug)]
+pub struct ShapeUnits {
+    /// How many world units make up a meter
+    pub world_units_per_meter: f32,
+    pub display: LengthUnit,
+}
+
+impl Default for ShapeUnits {
+    fn default() -> Self {
+        Self {
+            world_units_per_meter: 1.,
+            display: LengthUnit::Meter,
+        }
+    }
+}
+
+impl ShapeUnits {
+    /// How many world units make up one display unit
+    pub fn scale(&self) -> f32 {
+        self.world_units_per_meter * self.display.meters()
+    }
+
+    /// Converts a length in the display unit to world units
+    pub fn to_world(&self, length: f32) -> f32 {
+        length * self.scale()
+    }
+
+    /// Converts a length in world units to the display unit
+    pub fn to_display(&self, length: f32) -> f32 {
+        length / self.scale()
+    }
+
+    /// Converts a length in meters to world units, regardless of the display unit.
+    /// Used for the size of what the plugin shows, such as labels and handles
+    pub fn from_meters(&self, length: f32) -> f32 {
+        length * self.world_units_per_meter
+    }
+
+    /// Formats a length in world units in the display unit, such as `1.25 m`
+    pub fn format(&self, length: f32) -> String {
+        format!("{:.2} {}", self.to_display(length), self.display.symbol())
+    }
+}